@@ -0,0 +1,291 @@
+use serde_json::{Value, json};
+use std::io::{BufRead, IsTerminal};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::logic::history::{self, HistoryEntry, HistoryFilter};
+use crate::logic::lock::{self, LockState};
+use crate::logic::package::Package;
+use crate::logic::pacman_conf::PACMAN_CONF;
+use crate::logic::server::Server;
+use crate::logic::transaction::Transaction;
+
+const USAGE: &str = "\
+Usage: pacmanager-iced [--json] [--yes] <command> [arguments]
+Without a command the graphical interface is started.
+install, remove and upgrade ask for confirmation, --yes skips it and is required when stdin is not a terminal.
+
+Commands:
+  search <query>          Search the sync databases and foreign packages
+  info <package>          Show every field of a package
+  install <package>...    Install packages, \"repo/name\" pins a repository
+  remove <package>...     Remove packages
+  upgrade                 Upgrade the system, held packages are skipped
+  orphans                 List dependencies nothing requires anymore
+  history [package]       List the transactions in pacman.log, optionally touching a package";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliCommand {
+    Search(String),
+    Info(String),
+    Install(Vec<String>),
+    Remove(Vec<String>),
+    Upgrade,
+    Orphans,
+    History(Option<String>),
+    Help,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CliFlags {
+    pub json: bool,
+    //Changes the system without asking first
+    pub yes: bool,
+}
+
+//Arguments after the program name, the flags are accepted anywhere
+pub fn parse_args(args: &[String]) -> Result<(CliCommand, CliFlags), String> {
+    let flags = CliFlags {
+        json: args.iter().any(|a| a == "--json"),
+        yes: args.iter().any(|a| a == "--yes" || a == "-y"),
+    };
+    let args = args
+        .iter()
+        .filter(|a| !["--json", "--yes", "-y"].contains(&a.as_str()))
+        .cloned()
+        .collect::<Vec<_>>();
+    let Some((command, rest)) = args.split_first() else {
+        return Ok((CliCommand::Help, flags));
+    };
+
+    let command = match (command.as_str(), rest) {
+        ("search", query) if !query.is_empty() => CliCommand::Search(query.join(" ")),
+        ("info", [name]) => CliCommand::Info(name.clone()),
+        ("install", names) if !names.is_empty() => CliCommand::Install(names.to_vec()),
+        ("remove", names) if !names.is_empty() => CliCommand::Remove(names.to_vec()),
+        ("upgrade", []) => CliCommand::Upgrade,
+        ("orphans", []) => CliCommand::Orphans,
+        ("history", []) => CliCommand::History(None),
+        ("history", [name]) => CliCommand::History(Some(name.clone())),
+        ("help" | "--help" | "-h", _) => CliCommand::Help,
+        (command, _) => return Err(format!("Invalid use of \"{}\"\n\n{}", command, USAGE)),
+    };
+    Ok((command, flags))
+}
+
+fn package_json(package: &Package) -> Value {
+    Value::Object(package.get_properties().into_iter().map(|(k, v)| (k, Value::String(v))).collect())
+}
+
+fn package_line(package: &Package) -> String {
+    format!(
+        "{} {}{}",
+        package.get_qualified_name(),
+        package.get_property("Version".to_string()).unwrap_or_default(),
+        if package.is_installed() { " [installed]" } else { "" }
+    )
+}
+
+fn print_packages(packages: &[Arc<Mutex<Package>>], json: bool) {
+    if json {
+        let list = packages.iter().map(|p| package_json(&p.lock().unwrap())).collect::<Vec<_>>();
+        println!("{}", Value::Array(list));
+    } else {
+        packages.iter().for_each(|p| println!("{}", package_line(&p.lock().unwrap())));
+    }
+}
+
+fn history_json(entry: &HistoryEntry) -> Value {
+    json!({
+        "timestamp": entry.timestamp,
+        "command": entry.command,
+        "changes": entry.changes.iter().map(|c| json!({
+            "name": c.name,
+            "action": format!("{:?}", c.action).to_lowercase(),
+            "old_version": c.old_version,
+            "new_version": c.new_version,
+        })).collect::<Vec<_>>(),
+        "warnings": entry.warnings,
+    })
+}
+
+//Asks on stderr whether to go ahead, reading the answer from `input`. Without a terminal to ask on `--yes` is required
+fn confirm(flags: CliFlags, interactive: bool, input: &mut impl BufRead) -> Result<(), String> {
+    if flags.yes {
+        return Ok(());
+    }
+    if !interactive {
+        return Err("Not asking for confirmation without a terminal, pass --yes to proceed".to_string());
+    }
+    eprint!(":: Proceed? [y/N] ");
+    let mut answer = String::new();
+    input.read_line(&mut answer).map_err(|e| e.to_string())?;
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => Ok(()),
+        _ => Err("Aborted".to_string()),
+    }
+}
+
+fn confirm_on_stdin(flags: CliFlags) -> Result<(), String> {
+    let stdin = std::io::stdin();
+    confirm(flags, stdin.is_terminal(), &mut stdin.lock())
+}
+
+//Prints what the transaction resolves to and runs it once confirmed, pacman's defaults answer any question
+fn run_transaction(transaction: Transaction, flags: CliFlags) -> Result<(), String> {
+    let preview = transaction.preview()?;
+    if flags.json {
+        println!("{}", json!({ "install": preview.install, "remove": preview.remove }));
+    } else {
+        preview.remove.iter().for_each(|x| println!("remove {}", x));
+        preview.install.iter().for_each(|x| println!("install {}", x));
+    }
+    confirm_on_stdin(flags)?;
+    //Warnings such as "is up to date -- skipping" are passed on without failing the command
+    let warnings = transaction.commit_checked()?;
+    eprint!("{}", warnings);
+    Ok(())
+}
+
+pub fn execute(command: CliCommand, flags: CliFlags) -> Result<(), String> {
+    let json = flags.json;
+    match command {
+        CliCommand::Help => println!("{}", USAGE),
+        CliCommand::Search(query) => {
+            let server = Server::intialized();
+            print_packages(&server.search(query), json);
+        }
+        CliCommand::Info(name) => {
+            let server = Server::intialized();
+            let package = server.get_package(name.clone()).ok_or(format!("Package \"{}\" not found", name))?;
+            let package = package.lock().unwrap();
+            if json {
+                println!("{}", package_json(&package));
+            } else {
+                package.get_properties().iter().for_each(|(k, v)| println!("{:<16}: {}", k, v));
+            }
+        }
+        CliCommand::Install(names) => run_transaction(
+            Transaction {
+                description: format!("Install {}", names.join(", ")),
+                install: names,
+                ..Default::default()
+            },
+            flags,
+        )?,
+        CliCommand::Remove(names) => run_transaction(
+            Transaction {
+                description: format!("Remove {}", names.join(", ")),
+                remove: names,
+                ..Default::default()
+            },
+            flags,
+        )?,
+        CliCommand::Upgrade => {
+            let lock = lock::check(&lock::lock_file(), Path::new(lock::PROC_PATH));
+            if lock != LockState::Free {
+                return Err(lock.describe());
+            }
+            confirm_on_stdin(flags)?;
+            let (success, stderror) = Server::default().system_update_status();
+            if !success {
+                return Err(stderror);
+            }
+            eprint!("{}", stderror);
+        }
+        CliCommand::Orphans => {
+            let server = Server::intialized();
+            let mut orphans = server
+                .installed()
+                .into_iter()
+                .filter(|p| p.get_property("Orphan".to_string()).unwrap_or_default() == "True")
+                .map(|p| Arc::new(Mutex::new(p)))
+                .collect::<Vec<_>>();
+            orphans.sort_by_key(|p| p.lock().unwrap().get_property("Name".to_string()));
+            print_packages(&orphans, json);
+        }
+        CliCommand::History(package) => {
+            let filter = HistoryFilter {
+                package: package.unwrap_or_default(),
+                ..Default::default()
+            };
+            let entries = history::load(&history::log_path(Path::new(PACMAN_CONF)))?
+                .into_iter()
+                .filter(|e| filter.matches(e))
+                .collect::<Vec<_>>();
+            if json {
+                println!("{}", Value::Array(entries.iter().map(history_json).collect()));
+            } else {
+                for entry in entries {
+                    println!("[{}] {}", entry.timestamp, entry.command.clone().unwrap_or_default());
+                    entry.changes.iter().for_each(|c| println!("  {}", c.describe()));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+//Entry point of the headless mode, returns the process exit code. Errors always go to stderr
+pub fn run(args: &[String]) -> i32 {
+    let (command, flags) = match parse_args(args) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    match execute(command, flags) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e.trim_end());
+            1
+        }
+    }
+}
+
+#[test]
+fn test_parse_args() {
+    let args = |x: &[&str]| x.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(parse_args(&args(&[])), Ok((CliCommand::Help, CliFlags::default())));
+    assert_eq!(
+        parse_args(&args(&["search", "--json", "text", "editor"])),
+        Ok((CliCommand::Search("text editor".to_string()), CliFlags { json: true, yes: false }))
+    );
+    assert_eq!(
+        parse_args(&args(&["install", "extra/vim", "--yes", "gcc"])),
+        Ok((CliCommand::Install(args(&["extra/vim", "gcc"])), CliFlags { json: false, yes: true }))
+    );
+    assert_eq!(parse_args(&args(&["history"])), Ok((CliCommand::History(None), CliFlags::default())));
+    assert_eq!(parse_args(&args(&["history", "vim"])), Ok((CliCommand::History(Some("vim".to_string())), CliFlags::default())));
+    assert!(parse_args(&args(&["info"])).is_err());
+    assert!(parse_args(&args(&["upgrade", "now"])).is_err());
+    assert!(parse_args(&args(&["frobnicate"])).is_err());
+}
+
+#[test]
+fn test_confirm() {
+    let yes = CliFlags { yes: true, ..Default::default() };
+    let mut empty = std::io::Cursor::new("");
+    assert_eq!(confirm(yes, false, &mut empty), Ok(()));
+    assert!(confirm(CliFlags::default(), false, &mut std::io::Cursor::new("y\n")).unwrap_err().contains("--yes"));
+    assert_eq!(confirm(CliFlags::default(), true, &mut std::io::Cursor::new("Y\n")), Ok(()));
+    assert_eq!(confirm(CliFlags::default(), true, &mut std::io::Cursor::new("\n")), Err("Aborted".to_string()));
+    assert_eq!(confirm(CliFlags::default(), true, &mut empty), Err("Aborted".to_string()));
+}
+
+#[test]
+fn test_cli_output() {
+    let package = Package::from_pkginfo("pkgname = vim\npkgver = 9.1-1\n".to_string());
+    assert_eq!(package_line(&package), "vim 9.1-1");
+    assert_eq!(package_json(&package)["Version"], "9.1-1");
+
+    let entries = history::parse(
+        "[2024-05-01T10:00:00+0200] [PACMAN] Running 'pacman -S vim'\n[2024-05-01T10:00:05+0200] [ALPM] upgraded vim (9.0-1 -> 9.1-1)\n",
+    );
+    let json = history_json(&entries[0]);
+    assert_eq!(json["command"], "pacman -S vim");
+    assert_eq!(json["changes"][0]["action"], "upgraded");
+    assert_eq!(json["changes"][0]["old_version"], "9.0-1");
+}
@@ -0,0 +1,326 @@
+use std::{
+    fmt::Debug,
+    path::PathBuf,
+    process::Command,
+    sync::Arc,
+};
+
+use serde::Deserialize;
+
+use crate::logic::package::Package;
+
+pub const AUR_URL: &str = "https://aur.archlinux.org";
+
+//A package as returned by the AUR RPC (v5)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AurPackage {
+    pub name: String,
+    pub package_base: String,
+    pub version: String,
+    pub description: Option<String>,
+    #[serde(rename = "URL")]
+    pub url: Option<String>,
+    pub maintainer: Option<String>,
+    pub num_votes: u64,
+    pub popularity: f64,
+    pub out_of_date: Option<u64>,
+    #[serde(default)]
+    pub depends: Vec<String>,
+    #[serde(default)]
+    pub make_depends: Vec<String>,
+    #[serde(default)]
+    pub license: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    #[serde(rename = "type")]
+    kind: String,
+    error: Option<String>,
+    #[serde(default)]
+    results: Vec<AurPackage>,
+}
+
+impl AurPackage {
+    //Converts the result into a `Package` with the property names used by pacman
+    pub fn to_package(&self) -> Package {
+        let mut package = Package::default();
+        let mut set = |prop: &str, value: String| package.set_property(prop.to_string(), value);
+        set("Name", self.name.clone());
+        set("Version", self.version.clone());
+        set("Description", self.description.clone().unwrap_or_default());
+        set("URL", self.url.clone().unwrap_or_default());
+        set("Licenses", self.license.join("  "));
+        set("Depends On", self.depends.join("  "));
+        set("Make Deps", self.make_depends.join("  "));
+        set("Maintainer", self.maintainer.clone().unwrap_or("None (orphaned)".to_string()));
+        set("Votes", self.num_votes.to_string());
+        set("Package Base", self.package_base.clone());
+        set("AUR", "True".to_string());
+        set("Installed", "False".to_string());
+        package
+    }
+}
+
+//Everything the app needs from the AUR, abstracted so tests can point it at a local server
+pub trait AurBackend: Debug + Send + Sync {
+    fn search(&self, query: String) -> Result<Vec<AurPackage>, String>;
+    fn info(&self, names: Vec<String>) -> Result<Vec<AurPackage>, String>;
+    //Git URL the build directory is cloned from
+    fn clone_url(&self, package_base: String) -> String;
+}
+
+//Talks to an AUR instance over its RPC interface, requests are made with curl like the rest
+//of the app shells out to system tools
+#[derive(Debug, Clone)]
+pub struct AurRpc {
+    pub base_url: String,
+}
+
+impl Default for AurRpc {
+    fn default() -> Self {
+        AurRpc {
+            base_url: AUR_URL.to_string(),
+        }
+    }
+}
+
+fn url_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn fetch(url: String) -> Result<String, String> {
+    let output = Command::new("curl")
+        .args(["-fsSL", "--max-time", "30"])
+        .arg(&url)
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "Request to {} failed: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+impl AurRpc {
+    fn rpc(&self, url: String) -> Result<Vec<AurPackage>, String> {
+        let response: RpcResponse = serde_json::from_str(&fetch(url)?).map_err(|e| e.to_string())?;
+        if response.kind == "error" {
+            return Err(response.error.unwrap_or_default());
+        }
+        Ok(response.results)
+    }
+}
+
+impl AurBackend for AurRpc {
+    fn search(&self, query: String) -> Result<Vec<AurPackage>, String> {
+        //The RPC rejects queries shorter than two characters
+        if query.trim().len() < 2 {
+            return Ok(vec![]);
+        }
+        self.rpc(format!("{}/rpc/v5/search/{}?by=name-desc", self.base_url, url_encode(query.trim())))
+    }
+
+    fn info(&self, names: Vec<String>) -> Result<Vec<AurPackage>, String> {
+        if names.is_empty() {
+            return Ok(vec![]);
+        }
+        let args = names
+            .iter()
+            .map(|n| format!("arg[]={}", url_encode(n)))
+            .collect::<Vec<_>>()
+            .join("&");
+        self.rpc(format!("{}/rpc/v5/info?{}", self.base_url, args))
+    }
+
+    fn clone_url(&self, package_base: String) -> String {
+        format!("{}/{}.git", self.base_url, package_base)
+    }
+}
+
+//Shared handle to the configured backend so it can live inside cloneable widgets
+#[derive(Debug, Clone)]
+pub struct Aur(pub Arc<dyn AurBackend>);
+
+impl Default for Aur {
+    fn default() -> Self {
+        Aur(Arc::new(AurRpc::default()))
+    }
+}
+
+//Where AUR packages are cloned and built, per user since makepkg refuses to run as root
+pub fn build_root() -> PathBuf {
+    let cache = std::env::var("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(std::env::var("HOME").unwrap_or_default()).join(".cache"));
+    cache.join("pacmanager").join("aur")
+}
+
+//Result of preparing a build: either dependencies pacman has to install first or built archives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildOutcome {
+    //Unsatisfied dependencies, split between the ones the sync repositories provide and the ones that have to be built from the AUR first
+    MissingDependencies { repo: Vec<String>, aur: Vec<String> },
+    Built(Vec<String>),
+}
+
+fn run(command: &mut Command) -> Result<String, String> {
+    let output = command.output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+//Clones (or updates) the package base and returns the checked out commit with its PKGBUILD,
+//so what the user reviews is exactly what gets built
+pub fn checkout(aur: &dyn AurBackend, package_base: &str) -> Result<(String, String), String> {
+    let dir = build_root().join(package_base);
+
+    if dir.join(".git").exists() {
+        run(Command::new("git").arg("-C").arg(&dir).args(["pull", "--ff-only"]))?;
+    } else {
+        std::fs::create_dir_all(build_root()).map_err(|e| e.to_string())?;
+        run(Command::new("git")
+            .arg("clone")
+            .arg(aur.clone_url(package_base.to_string()))
+            .arg(&dir))?;
+    }
+
+    let commit = run(Command::new("git").arg("-C").arg(&dir).args(["rev-parse", "HEAD"]))?;
+    let pkgbuild = std::fs::read_to_string(dir.join("PKGBUILD")).map_err(|e| e.to_string())?;
+    Ok((commit.trim().to_string(), pkgbuild))
+}
+
+//Reads the dependencies needed to build and install from a .SRCINFO, including the ones for the current architecture
+pub fn srcinfo_depends(srcinfo: &str) -> Vec<String> {
+    let mut deps = Vec::new();
+    for line in srcinfo.lines() {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        let key = key
+            .strip_suffix(std::env::consts::ARCH)
+            .and_then(|k| k.strip_suffix('_'))
+            .unwrap_or(key);
+        if ["depends", "makedepends", "checkdepends"].contains(&key) {
+            let value = value.trim().to_string();
+            if !value.is_empty() && !deps.contains(&value) {
+                deps.push(value);
+            }
+        }
+    }
+    deps
+}
+
+//Builds the reviewed `commit` of the clone made by `checkout` with makepkg as the current user.
+//Dependencies are read from the .SRCINFO of that commit and are not installed by makepkg since it would need a terminal for sudo,
+//they are reported back so they go through the normal transaction pipeline instead
+pub fn build(package_base: &str, commit: &str) -> Result<BuildOutcome, String> {
+    let dir = build_root().join(package_base);
+
+    //Anything pulled since the review is discarded
+    run(Command::new("git").arg("-C").arg(&dir).args(["reset", "--hard", commit]))?;
+
+    let srcinfo = std::fs::read_to_string(dir.join(".SRCINFO")).map_err(|e| e.to_string())?;
+    let deps = srcinfo_depends(&srcinfo);
+    //`pacman -T` prints the dependencies that are not satisfied and exits with 127 when there are any
+    let missing = Command::new("pacman")
+        .arg("-T")
+        .args(&deps)
+        .output()
+        .map_err(|e| e.to_string())?;
+    let (repo, aur): (Vec<String>, Vec<String>) = String::from_utf8_lossy(&missing.stdout)
+        .lines()
+        .filter(|x| !x.trim().is_empty())
+        .map(|x| x.to_string())
+        //A dependency some sync package satisfies resolves with `pacman -Sddp`, anything else has to come from the AUR
+        .partition(|dep| {
+            Command::new("pacman")
+                .args(["-Sddp", "--print-format", "%n", dep])
+                .output()
+                .is_ok_and(|o| o.status.success())
+        });
+    if !repo.is_empty() || !aur.is_empty() {
+        return Ok(BuildOutcome::MissingDependencies { repo, aur });
+    }
+
+    run(Command::new("makepkg")
+        .current_dir(&dir)
+        .args(["--force", "--cleanbuild", "--noconfirm"]))?;
+    let archives = run(Command::new("makepkg").current_dir(&dir).arg("--packagelist"))?;
+
+    Ok(BuildOutcome::Built(
+        archives
+            .lines()
+            .filter(|x| std::path::Path::new(x).exists())
+            .map(|x| x.to_string())
+            .collect(),
+    ))
+}
+
+#[test]
+fn test_srcinfo_depends() {
+    let srcinfo = format!(
+        "pkgbase = example\n\tpkgver = 1.0\n\tmakedepends = cargo\n\tdepends = glibc\n\tdepends = libfoo>=2\n\tdepends_{} = libarch\n\tdepends_none = libother\n\noptdepends = extra: not needed\n\npkgname = example\n\tdepends = glibc\n\tcheckdepends = python\n",
+        std::env::consts::ARCH
+    );
+    assert_eq!(srcinfo_depends(&srcinfo), vec!["cargo", "glibc", "libfoo>=2", "libarch", "python"]);
+}
+
+#[test]
+fn test_aur_rpc() {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().take(2) {
+            let mut stream = stream.unwrap();
+            let mut request = [0; 1024];
+            let len = stream.read(&mut request).unwrap();
+            let request = String::from_utf8_lossy(&request[..len]).to_string();
+
+            let body = if request.starts_with("GET /rpc/v5/search/yay") {
+                r#"{"resultcount":1,"results":[{"Name":"yay","PackageBase":"yay","Version":"12.4.2-1",
+                    "Description":"Yet another yogurt","URL":null,"Maintainer":"jguer","NumVotes":2000,
+                    "Popularity":30.5,"OutOfDate":null}],"type":"search","version":5}"#
+            } else {
+                r#"{"resultcount":0,"results":[],"type":"error","version":5,"error":"Incorrect request type specified."}"#
+            };
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        }
+    });
+
+    let aur = AurRpc {
+        base_url: format!("http://127.0.0.1:{}", port),
+    };
+
+    let results = aur.search("yay".to_string()).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].version, "12.4.2-1");
+    assert_eq!(
+        results[0].to_package().get_property("AUR".to_string()),
+        Some("True".to_string())
+    );
+
+    assert!(aur.info(vec!["missing".to_string()]).is_err());
+}
@@ -0,0 +1,185 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use crate::logic::privileged;
+use crate::logic::transaction::shell_quote;
+use crate::logic::version::vercmp;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedPackage {
+    pub name: String,
+    pub version: String,
+    pub arch: String,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+//Every cached version of a package, newest first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheGroup {
+    pub name: String,
+    pub versions: Vec<CachedPackage>,
+}
+
+impl CacheGroup {
+    pub fn total_size(&self) -> u64 {
+        self.versions.iter().map(|v| v.size).sum()
+    }
+}
+
+//Same knobs as paccache: keep the `keep` newest versions, optionally only touching uninstalled packages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CleanupPolicy {
+    pub keep: usize,
+    pub only_uninstalled: bool,
+}
+
+impl Default for CleanupPolicy {
+    fn default() -> Self {
+        //paccache's default
+        CleanupPolicy {
+            keep: 3,
+            only_uninstalled: false,
+        }
+    }
+}
+
+//Splits "name-pkgver-pkgrel-arch.pkg.tar.zst", names can contain dashes so it is parsed from the end
+pub fn parse_filename(file: &str) -> Option<(String, String, String)> {
+    let stem = &file[..file.find(".pkg.tar")?];
+    if file.ends_with(".sig") || file.ends_with(".part") {
+        return None;
+    }
+    let mut parts = stem.rsplitn(4, '-');
+    let arch = parts.next()?;
+    let rel = parts.next()?;
+    let ver = parts.next()?;
+    let name = parts.next()?;
+    Some((name.to_string(), format!("{}-{}", ver, rel), arch.to_string()))
+}
+
+pub fn scan(dirs: &[PathBuf]) -> Vec<CacheGroup> {
+    let mut groups: BTreeMap<String, Vec<CachedPackage>> = BTreeMap::new();
+
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file = entry.file_name().to_string_lossy().to_string();
+            let Some((name, version, arch)) = parse_filename(&file) else {
+                continue;
+            };
+            let size = entry.metadata().map(|m| m.len()).unwrap_or_default();
+            groups.entry(name.clone()).or_default().push(CachedPackage {
+                name,
+                version,
+                arch,
+                path: entry.path(),
+                size,
+            });
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(name, mut versions)| {
+            versions.sort_by(|a, b| vercmp(&b.version, &a.version));
+            CacheGroup { name, versions }
+        })
+        .collect()
+}
+
+//Files the policy would delete, `installed` holds the names of installed packages
+pub fn plan_cleanup(groups: &[CacheGroup], policy: CleanupPolicy, installed: &HashSet<String>) -> Vec<CachedPackage> {
+    groups
+        .iter()
+        .filter(|g| !policy.only_uninstalled || !installed.contains(&g.name))
+        .flat_map(|g| g.versions.iter().skip(policy.keep).cloned())
+        .collect()
+}
+
+//Deletes the archives together with their detached signatures
+pub fn remove(packages: &[CachedPackage]) -> String {
+    if packages.is_empty() {
+        return String::new();
+    }
+    let targets = packages
+        .iter()
+        .map(|p| {
+            let path = p.path.to_string_lossy();
+            format!("{} {}", shell_quote(&path), shell_quote(&format!("{}.sig", path)))
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    privileged::run(format!("rm -f {}", targets))
+}
+
+//Cached archives of `name`, newest first, each compared with `current` (older versions are Less)
+pub fn cached_versions(dirs: &[PathBuf], name: &str, current: &str) -> Vec<(CachedPackage, std::cmp::Ordering)> {
+    scan(dirs)
+        .into_iter()
+        .find(|g| g.name == name)
+        .map(|g| g.versions)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|p| {
+            let ordering = vercmp(&p.version, current);
+            (p, ordering)
+        })
+        .collect()
+}
+
+pub fn cache_dirs(conf: &Path) -> Vec<PathBuf> {
+    crate::logic::pacman_conf::cache_dirs(conf)
+        .into_iter()
+        .map(PathBuf::from)
+        .collect()
+}
+
+#[test]
+fn test_cache() {
+    assert_eq!(
+        parse_filename("lib32-gcc-libs-14.2.1+r134-1-x86_64.pkg.tar.zst"),
+        Some(("lib32-gcc-libs".to_string(), "14.2.1+r134-1".to_string(), "x86_64".to_string()))
+    );
+    assert_eq!(parse_filename("vim-9.1-1-x86_64.pkg.tar.zst.sig"), None);
+
+    let dir = std::env::temp_dir().join(format!("pacmanager-cache-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for file in [
+        "vim-9.0-1-x86_64.pkg.tar.zst",
+        "vim-9.1-1-x86_64.pkg.tar.zst",
+        "vim-9.1-2-x86_64.pkg.tar.zst",
+        "nano-8.0-1-x86_64.pkg.tar.zst",
+    ] {
+        std::fs::write(dir.join(file), "1234").unwrap();
+    }
+
+    let groups = scan(std::slice::from_ref(&dir));
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[1].name, "vim");
+    assert_eq!(groups[1].versions[0].version, "9.1-2");
+    assert_eq!(groups[1].total_size(), 12);
+
+    let installed = HashSet::from(["vim".to_string()]);
+    let keep_one = plan_cleanup(&groups, CleanupPolicy { keep: 1, only_uninstalled: false }, &installed);
+    assert_eq!(keep_one.len(), 2);
+    let uninstalled = plan_cleanup(&groups, CleanupPolicy { keep: 0, only_uninstalled: true }, &installed);
+    assert_eq!(uninstalled.len(), 1);
+    assert_eq!(uninstalled[0].name, "nano");
+
+    let versions = cached_versions(std::slice::from_ref(&dir), "vim", "9.1-1");
+    assert_eq!(
+        versions.iter().map(|(p, o)| (p.version.as_str(), *o)).collect::<Vec<_>>(),
+        vec![
+            ("9.1-2", std::cmp::Ordering::Greater),
+            ("9.1-1", std::cmp::Ordering::Equal),
+            ("9.0-1", std::cmp::Ordering::Less),
+        ]
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
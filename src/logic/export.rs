@@ -0,0 +1,198 @@
+use serde_json::{Map, Value};
+use std::path::Path;
+
+use crate::logic::package::Package;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExportColumn {
+    Name,
+    Version,
+    Repository,
+    Size,
+    InstallDate,
+    Reason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+    //One name per line, what `pacman -S - < list` reads
+    Names,
+}
+
+impl ExportColumn {
+    pub const ALL: [ExportColumn; 6] = [
+        ExportColumn::Name,
+        ExportColumn::Version,
+        ExportColumn::Repository,
+        ExportColumn::Size,
+        ExportColumn::InstallDate,
+        ExportColumn::Reason,
+    ];
+
+    //Used as the JSON key and the CSV header
+    pub fn key(&self) -> &'static str {
+        match self {
+            ExportColumn::Name => "name",
+            ExportColumn::Version => "version",
+            ExportColumn::Repository => "repository",
+            ExportColumn::Size => "size",
+            ExportColumn::InstallDate => "install_date",
+            ExportColumn::Reason => "reason",
+        }
+    }
+
+    pub fn value(&self, package: &Package) -> String {
+        let property = |name: &str| package.get_property(name.to_string()).unwrap_or_default();
+        match self {
+            ExportColumn::Name => property("Name"),
+            ExportColumn::Version => property("Version"),
+            ExportColumn::Repository => property("Repository"),
+            ExportColumn::Size => property("Installed Size"),
+            ExportColumn::InstallDate => property("Install Date"),
+            //"Explicitly installed" or "Installed as a dependency for another package", empty when not installed
+            ExportColumn::Reason => match property("Install Reason") {
+                r if r.starts_with("Explicitly") => "explicit".to_string(),
+                r if r.contains("dependency") => "dependency".to_string(),
+                _ => String::new(),
+            },
+        }
+    }
+}
+
+//Format and columns picked in the search view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    pub columns: Vec<ExportColumn>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            format: ExportFormat::default(),
+            columns: vec![ExportColumn::Name, ExportColumn::Version, ExportColumn::Repository],
+        }
+    }
+}
+
+impl ExportOptions {
+    //Keeps the columns in their canonical order whatever order they were toggled in
+    pub fn set_column(&mut self, column: ExportColumn, enabled: bool) {
+        self.columns.retain(|c| *c != column);
+        if enabled {
+            self.columns.push(column);
+        }
+        self.columns.sort_by_key(|c| ExportColumn::ALL.iter().position(|x| x == c));
+    }
+}
+
+impl std::fmt::Display for ExportColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportColumn::Name => write!(f, "Name"),
+            ExportColumn::Version => write!(f, "Version"),
+            ExportColumn::Repository => write!(f, "Repository"),
+            ExportColumn::Size => write!(f, "Size"),
+            ExportColumn::InstallDate => write!(f, "Install date"),
+            ExportColumn::Reason => write!(f, "Reason"),
+        }
+    }
+}
+
+impl std::fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportFormat::Json => write!(f, "JSON"),
+            ExportFormat::Csv => write!(f, "CSV"),
+            ExportFormat::Names => write!(f, "Name list"),
+        }
+    }
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Json, ExportFormat::Csv, ExportFormat::Names];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Names => "txt",
+        }
+    }
+}
+
+//Quotes a CSV field when it holds a separator, a quote or a line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+//`columns` are ignored by the name list, which only carries names
+pub fn render(packages: &[Package], columns: &[ExportColumn], format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Json => {
+            let rows = packages
+                .iter()
+                .map(|p| Value::Object(columns.iter().map(|c| (c.key().to_string(), Value::String(c.value(p)))).collect::<Map<_, _>>()))
+                .collect::<Vec<_>>();
+            serde_json::to_string_pretty(&Value::Array(rows)).unwrap_or_default()
+        }
+        ExportFormat::Csv => {
+            let mut lines = vec![columns.iter().map(|c| c.key()).collect::<Vec<_>>().join(",")];
+            lines.extend(
+                packages
+                    .iter()
+                    .map(|p| columns.iter().map(|c| csv_field(&c.value(p))).collect::<Vec<_>>().join(",")),
+            );
+            lines.join("\n") + "\n"
+        }
+        ExportFormat::Names => packages.iter().map(|p| ExportColumn::Name.value(p) + "\n").collect(),
+    }
+}
+
+pub fn write(path: &Path, packages: &[Package], columns: &[ExportColumn], format: ExportFormat) -> Result<(), String> {
+    std::fs::write(path, render(packages, columns, format)).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+#[test]
+fn test_export() {
+    let raw = |lines: &[&str]| Package::from_raw(lines.iter().map(|x| x.to_string()).collect());
+    let packages = vec![
+        raw(&[
+            "Repository      : extra",
+            "Name            : vim",
+            "Version         : 9.1.0-1",
+            "Installed Size  : 4.50 MiB",
+            "Install Reason  : Explicitly installed",
+        ]),
+        raw(&["Name            : odd,name", "Version         : 1\"2", "Install Reason  : Installed as a dependency for another package"]),
+    ];
+    let columns = [ExportColumn::Name, ExportColumn::Version, ExportColumn::Reason];
+
+    assert_eq!(render(&packages, &columns, ExportFormat::Names), "vim\nodd,name\n");
+    assert_eq!(
+        render(&packages, &columns, ExportFormat::Csv),
+        "name,version,reason\nvim,9.1.0-1,explicit\n\"odd,name\",\"1\"\"2\",dependency\n"
+    );
+
+    let json: Value = serde_json::from_str(&render(&packages, &columns, ExportFormat::Json)).unwrap();
+    assert_eq!(json[0]["name"], "vim");
+    assert_eq!(json[0]["reason"], "explicit");
+    assert!(json[0].get("size").is_none());
+    assert_eq!(ExportColumn::Size.value(&packages[0]), "4.50 MiB");
+
+    let mut options = ExportOptions::default();
+    options.set_column(ExportColumn::Reason, true);
+    options.set_column(ExportColumn::Version, false);
+    options.set_column(ExportColumn::Size, true);
+    assert_eq!(
+        options.columns,
+        vec![ExportColumn::Name, ExportColumn::Repository, ExportColumn::Size, ExportColumn::Reason]
+    );
+}
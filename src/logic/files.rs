@@ -0,0 +1,250 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+//Default pacman database directory, holds one folder per installed package under "local"
+pub const DB_PATH: &str = "/var/lib/pacman";
+
+//A node of a package's file tree. Directories carry the total size of their contents
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileNode {
+    pub name: String,
+    //Absolute path, directories end with "/" like in pacman's listings
+    pub path: String,
+    pub size: Option<u64>,
+    pub children: BTreeMap<String, FileNode>,
+}
+
+impl FileNode {
+    pub fn is_dir(&self) -> bool {
+        self.path.ends_with("/")
+    }
+
+    //Number of regular files below this node
+    pub fn file_count(&self) -> usize {
+        if !self.is_dir() {
+            return 1;
+        }
+        self.children.values().map(|c| c.file_count()).sum()
+    }
+
+    //Builds the tree from pacman's flat listing, entries ending in "/" are directories
+    pub fn from_entries(entries: Vec<(String, Option<u64>)>) -> FileNode {
+        let mut root = FileNode {
+            name: "/".to_string(),
+            path: "/".to_string(),
+            ..Default::default()
+        };
+
+        for (path, size) in entries {
+            let is_dir = path.ends_with("/");
+            let parts = path
+                .split("/")
+                .filter(|x| !x.is_empty())
+                .collect::<Vec<_>>();
+
+            let mut node = &mut root;
+            let mut current = "/".to_string();
+            for (i, part) in parts.iter().enumerate() {
+                let last = i == parts.len() - 1;
+                current += part;
+                if !last || is_dir {
+                    current += "/";
+                }
+                node = node
+                    .children
+                    .entry(part.to_string())
+                    .or_insert_with(|| FileNode {
+                        name: part.to_string(),
+                        path: current.clone(),
+                        ..Default::default()
+                    });
+            }
+            if !is_dir {
+                node.size = size;
+            }
+        }
+
+        root.compute_sizes();
+        root
+    }
+
+    fn compute_sizes(&mut self) -> Option<u64> {
+        if self.is_dir() {
+            //Unknown sizes (files database listings, missing files) are left out of the total
+            let sizes = self
+                .children
+                .values_mut()
+                .filter_map(|c| c.compute_sizes())
+                .collect::<Vec<_>>();
+            self.size = if sizes.is_empty() { None } else { Some(sizes.into_iter().sum()) };
+        }
+        self.size
+    }
+}
+
+//Human readable size using the same binary units as pacman
+pub fn format_size(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, units[0])
+    } else {
+        format!("{:.2} {}", size, units[unit])
+    }
+}
+
+fn list(args: &[&str], name: String) -> Result<Vec<String>, String> {
+    let output = Command::new("pacman")
+        .args(args)
+        .arg(&name)
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+
+    //Lines look like "name /usr/bin/foo", the files database omits the leading slash
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|x| x.split_once(" "))
+        .map(|(_, path)| format!("/{}", path.trim_start_matches("/")))
+        .collect())
+}
+
+//Files owned by an installed package (`pacman -Ql`), sized from the filesystem
+pub fn installed_files(name: String) -> Result<FileNode, String> {
+    let entries = list(&["-Ql"], name)?
+        .into_iter()
+        .map(|path| {
+            let size = std::fs::symlink_metadata(&path).ok().map(|m| m.len());
+            (path, size)
+        })
+        .collect();
+    Ok(FileNode::from_entries(entries))
+}
+
+//Files of a package from the files database (`pacman -Fl`), which has no sizes
+pub fn remote_files(name: String) -> Result<FileNode, String> {
+    let entries = list(&["-Fl"], name)?;
+    if entries.is_empty() {
+        return Err("The files database has no entry for this package, it can be downloaded with `pacman -Fy`".to_string());
+    }
+    Ok(FileNode::from_entries(entries.into_iter().map(|p| (p, None)).collect()))
+}
+
+//Turns user input into the path to look up: absolute paths have their directory resolved
+//(so /bin/ls matches /usr/bin/ls) and bare command names are searched in $PATH
+pub fn resolve_target(target: &str) -> Option<PathBuf> {
+    let target = target.trim();
+    if target.contains("/") {
+        let path = PathBuf::from(target);
+        let parent = path.parent()?.canonicalize().ok()?;
+        return Some(parent.join(path.file_name()?));
+    }
+    std::env::var("PATH")
+        .ok()?
+        .split(":")
+        .map(|dir| PathBuf::from(dir).join(target))
+        .find(|candidate| candidate.exists())
+        .and_then(|candidate| resolve_target(&candidate.to_string_lossy()))
+}
+
+//Reads one "%SECTION%" of a local database entry (desc or files)
+fn db_section(content: &str, section: &str) -> Vec<String> {
+    content
+        .lines()
+        .skip_while(|x| *x != section)
+        .skip(1)
+        .take_while(|x| !x.is_empty())
+        .map(|x| x.to_string())
+        .collect()
+}
+
+//Name of the installed package that owns `path`, read straight from the local database's file lists
+//(the data `pacman -Qo` uses) so no process has to be spawned
+pub fn owner_in_db(db_path: &Path, path: &Path) -> Option<String> {
+    owners_in_db(db_path, &[path.to_path_buf()]).remove(path)
+}
+
+//Owners of several paths in one pass over the local database, paths nobody owns are left out
+pub fn owners_in_db(db_path: &Path, paths: &[PathBuf]) -> HashMap<PathBuf, String> {
+    let mut wanted = paths
+        .iter()
+        .map(|p| (p.to_string_lossy().trim_start_matches("/").trim_end_matches("/").to_string(), p.clone()))
+        .collect::<HashMap<_, _>>();
+    let mut owners = HashMap::new();
+    let Ok(entries) = std::fs::read_dir(db_path.join("local")) else {
+        return owners;
+    };
+
+    for entry in entries.flatten() {
+        if wanted.is_empty() {
+            break;
+        }
+        let Ok(file_list) = std::fs::read_to_string(entry.path().join("files")) else {
+            continue;
+        };
+        let owned = db_section(&file_list, "%FILES%")
+            .iter()
+            .filter_map(|f| wanted.remove(f.trim_end_matches("/")))
+            .collect::<Vec<_>>();
+        if owned.is_empty() {
+            continue;
+        }
+        let Some(name) = std::fs::read_to_string(entry.path().join("desc"))
+            .ok()
+            .and_then(|desc| db_section(&desc, "%NAME%").first().cloned())
+        else {
+            continue;
+        };
+        owners.extend(owned.into_iter().map(|p| (p, name.clone())));
+    }
+    owners
+}
+
+#[test]
+fn test_owner_in_db() {
+    let db = std::env::temp_dir().join(format!("pacmanager-owner-{}", std::process::id()));
+    let entry = db.join("local").join("vim-9.1.0-1");
+    std::fs::create_dir_all(&entry).unwrap();
+    std::fs::write(entry.join("desc"), "%NAME%\nvim\n\n%VERSION%\n9.1.0-1\n").unwrap();
+    std::fs::write(entry.join("files"), "%FILES%\nusr/\nusr/bin/\nusr/bin/vim\n\n%BACKUP%\netc/vimrc\tabc\n").unwrap();
+
+    assert_eq!(owner_in_db(&db, Path::new("/usr/bin/vim")), Some("vim".to_string()));
+    assert_eq!(owner_in_db(&db, Path::new("/usr/bin/nano")), None);
+    assert_eq!(owner_in_db(&db, Path::new("/etc/vimrc")), None);
+    let owners = owners_in_db(&db, &[PathBuf::from("/usr/bin/vim"), PathBuf::from("/usr/bin/nano")]);
+    assert_eq!(owners.len(), 1);
+    assert_eq!(owners[Path::new("/usr/bin/vim")], "vim");
+
+    std::fs::remove_dir_all(&db).unwrap();
+}
+
+#[test]
+fn test_file_tree() {
+    let tree = FileNode::from_entries(vec![
+        ("/usr/".to_string(), None),
+        ("/usr/bin/".to_string(), None),
+        ("/usr/bin/vim".to_string(), Some(100)),
+        ("/usr/bin/vimdiff".to_string(), Some(5)),
+        ("/usr/share/vim/vimrc".to_string(), Some(20)),
+    ]);
+
+    let usr = &tree.children["usr"];
+    assert!(usr.is_dir());
+    assert_eq!(usr.size, Some(125));
+    assert_eq!(usr.file_count(), 3);
+    assert_eq!(usr.children["bin"].children["vim"].path, "/usr/bin/vim");
+    assert_eq!(usr.children["share"].children["vim"].path, "/usr/share/vim/");
+    assert_eq!(tree.size, Some(125));
+    assert_eq!(format_size(125), "125 B");
+    assert_eq!(format_size(3 * 1024 * 1024 / 2), "1.50 MiB");
+}
@@ -0,0 +1,206 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, Mutex},
+};
+
+use crate::logic::package::Package;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Copy)]
+pub enum InstalledFacet {
+    #[default]
+    Any,
+    Installed,
+    NotInstalled,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Copy)]
+pub enum ReasonFacet {
+    #[default]
+    Any,
+    Explicit,
+    Dependency,
+}
+
+//A set of facets combined with AND. Facets holding several values (repositories) match any of them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    pub repositories: BTreeSet<String>,
+    pub installed: InstalledFacet,
+    pub reason: ReasonFacet,
+    pub upgradable: bool,
+    pub orphan: bool,
+    pub foreign: bool,
+    pub group: Option<String>,
+    pub architecture: Option<String>,
+    pub license: Option<String>,
+}
+
+//Number of packages carrying each facet value, used to label the filter options
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FacetCounts {
+    pub repositories: BTreeMap<String, usize>,
+    pub installed: usize,
+    pub not_installed: usize,
+    pub explicit: usize,
+    pub dependency: usize,
+    pub upgradable: usize,
+    pub orphan: usize,
+    pub foreign: usize,
+    pub groups: BTreeMap<String, usize>,
+    pub architectures: BTreeMap<String, usize>,
+    pub licenses: BTreeMap<String, usize>,
+}
+
+fn is_true(package: &Package, prop: &str) -> bool {
+    package.get_property(prop.to_string()).unwrap_or_default() == "True"
+}
+
+fn is_explicit(package: &Package) -> bool {
+    package
+        .get_property("Install Reason".to_string())
+        .unwrap_or_default()
+        .starts_with("Explicitly")
+}
+
+impl Filter {
+    pub fn matches(&self, package: &Package) -> bool {
+        if !self.repositories.is_empty()
+            && !self
+                .repositories
+                .contains(&package.get_property("Repository".to_string()).unwrap_or_default())
+        {
+            return false;
+        }
+
+        let installed = package.is_installed();
+        let installed_ok = match self.installed {
+            InstalledFacet::Any => true,
+            InstalledFacet::Installed => installed,
+            InstalledFacet::NotInstalled => !installed,
+        };
+        let reason_ok = match self.reason {
+            ReasonFacet::Any => true,
+            ReasonFacet::Explicit => installed && is_explicit(package),
+            ReasonFacet::Dependency => installed && !is_explicit(package),
+        };
+
+        let list_ok = |selected: &Option<String>, prop: &str| match selected {
+            Some(value) => package.get_list_property(prop.to_string()).contains(value),
+            None => true,
+        };
+
+        installed_ok
+            && reason_ok
+            && (!self.upgradable || is_true(package, "Upgradable"))
+            && (!self.orphan || is_true(package, "Orphan"))
+            && (!self.foreign || package.is_foreign())
+            && list_ok(&self.group, "Groups")
+            && list_ok(&self.architecture, "Architecture")
+            && list_ok(&self.license, "Licenses")
+    }
+}
+
+impl FacetCounts {
+    pub fn from_packages(packages: &[Arc<Mutex<Package>>]) -> FacetCounts {
+        let mut counts = FacetCounts::default();
+
+        for p in packages {
+            let package = p.lock().unwrap();
+
+            if let Some(repo) = package.get_property("Repository".to_string()) {
+                *counts.repositories.entry(repo).or_default() += 1;
+            }
+
+            if package.is_installed() {
+                counts.installed += 1;
+                if is_explicit(&package) {
+                    counts.explicit += 1;
+                } else {
+                    counts.dependency += 1;
+                }
+            } else {
+                counts.not_installed += 1;
+            }
+
+            counts.upgradable += is_true(&package, "Upgradable") as usize;
+            counts.orphan += is_true(&package, "Orphan") as usize;
+            counts.foreign += package.is_foreign() as usize;
+
+            for group in package.get_list_property("Groups".to_string()) {
+                *counts.groups.entry(group).or_default() += 1;
+            }
+            for arch in package.get_list_property("Architecture".to_string()) {
+                *counts.architectures.entry(arch).or_default() += 1;
+            }
+            for license in package.get_list_property("Licenses".to_string()) {
+                *counts.licenses.entry(license).or_default() += 1;
+            }
+        }
+
+        counts
+    }
+}
+
+#[test]
+fn test_filter() {
+    let raw = |lines: &[&str]| Package::from_raw(lines.iter().map(|x| x.to_string()).collect());
+
+    let vim = Arc::new(Mutex::new(raw(&[
+        "Repository      : extra",
+        "Name            : vim",
+        "Groups          : None",
+        "Architecture    : x86_64",
+        "Licenses        : custom:vim",
+        "Installed       : True",
+        "Install Reason  : Explicitly installed",
+        "Upgradable      : True",
+    ])));
+    let gcc = Arc::new(Mutex::new(raw(&[
+        "Repository      : core",
+        "Name            : gcc",
+        "Groups          : base-devel",
+        "Architecture    : x86_64",
+        "Licenses        : GPL-3.0-or-later",
+        "Installed       : True",
+        "Install Reason  : Installed as a dependency for another package",
+    ])));
+    let yay = Arc::new(Mutex::new(raw(&[
+        "Name            : yay",
+        "Architecture    : x86_64",
+        "Installed       : True",
+        "Install Reason  : Explicitly installed",
+        "Foreign         : True",
+    ])));
+    let packages = vec![vim.clone(), gcc.clone(), yay.clone()];
+
+    let counts = FacetCounts::from_packages(&packages);
+    assert_eq!(counts.repositories.get("extra"), Some(&1));
+    assert_eq!(counts.explicit, 2);
+    assert_eq!(counts.dependency, 1);
+    assert_eq!(counts.foreign, 1);
+    assert_eq!(counts.groups.get("base-devel"), Some(&1));
+    assert_eq!(counts.architectures.get("x86_64"), Some(&3));
+
+    let mut filter = Filter::default();
+    assert!(packages.iter().all(|p| filter.matches(&p.lock().unwrap())));
+
+    filter.reason = ReasonFacet::Explicit;
+    filter.upgradable = true;
+    assert!(filter.matches(&vim.lock().unwrap()));
+    assert!(!filter.matches(&yay.lock().unwrap()));
+
+    let filter = Filter {
+        repositories: BTreeSet::from(["core".to_string(), "extra".to_string()]),
+        group: Some("base-devel".to_string()),
+        ..Default::default()
+    };
+    assert!(filter.matches(&gcc.lock().unwrap()));
+    assert!(!filter.matches(&vim.lock().unwrap()));
+
+    let filter = Filter {
+        foreign: true,
+        ..Default::default()
+    };
+    assert!(filter.matches(&yay.lock().unwrap()));
+    assert!(!filter.matches(&gcc.lock().unwrap()));
+}
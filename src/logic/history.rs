@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::logic::cache::CacheGroup;
+use crate::logic::pacman_conf;
+use crate::logic::transaction::Transaction;
+
+pub const LOG_PATH: &str = "/var/log/pacman.log";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeAction {
+    Installed,
+    Upgraded,
+    Downgraded,
+    Reinstalled,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageChange {
+    pub name: String,
+    pub action: ChangeAction,
+    //Version before the transaction, None for installs
+    pub old_version: Option<String>,
+    //Version after the transaction, None for removals
+    pub new_version: Option<String>,
+}
+
+//One pacman run as recorded in pacman.log
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub timestamp: String,
+    //The command line, missing when the transaction was started by another frontend
+    pub command: Option<String>,
+    pub changes: Vec<PackageChange>,
+    pub warnings: Vec<String>,
+}
+
+//Keeps entries touching `package` (substring match) within the inclusive "YYYY-MM-DD" range, empty fields match everything
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryFilter {
+    pub package: String,
+    pub from: String,
+    pub to: String,
+}
+
+impl PackageChange {
+    pub fn describe(&self) -> String {
+        let versions = match (&self.old_version, &self.new_version) {
+            (Some(old), Some(new)) if old != new => format!("{} -> {}", old, new),
+            (_, Some(version)) | (Some(version), None) => version.clone(),
+            (None, None) => String::new(),
+        };
+        format!("{:?} {} {}", self.action, self.name, versions).to_lowercase()
+    }
+}
+
+impl HistoryEntry {
+    //"2024-05-01" for both the current ISO timestamps and the older "2019-01-01 10:00" ones
+    pub fn date(&self) -> &str {
+        self.timestamp.get(..10).unwrap_or(&self.timestamp)
+    }
+
+    pub fn change_for(&self, name: &str) -> Option<&PackageChange> {
+        self.changes.iter().find(|c| c.name == name)
+    }
+
+    //Names given on the command line, packages the transaction touched without being named were pulled in as dependencies
+    fn targets(&self) -> Option<Vec<String>> {
+        let command = self.command.as_ref()?;
+        Some(
+            command
+                .split_whitespace()
+                .skip(1)
+                .filter(|w| !w.starts_with('-'))
+                .map(|w| w.trim_matches('\'').rsplit('/').next().unwrap_or_default().to_string())
+                .collect(),
+        )
+    }
+
+    //The transaction reverting this one: installs are removed, and upgrades, downgrades and removals go back to the
+    //previous version from the cache. Removed packages missing from the cache are reinstalled from the repositories.
+    //`installed` maps the names installed now to their version, changes that were already undone or built upon are
+    //left alone with a note. Removed packages that were not named on the command line come back as dependencies
+    pub fn undo(&self, cache: &[CacheGroup], installed: &HashMap<String, String>) -> Transaction {
+        let mut transaction = Transaction {
+            description: format!("Undo the transaction of {}", self.timestamp),
+            remove_last: true,
+            ..Default::default()
+        };
+        let cached = |name: &str, version: &str| {
+            cache
+                .iter()
+                .find(|g| g.name == name)
+                .and_then(|g| g.versions.iter().find(|v| v.version == version))
+                .map(|v| v.path.to_string_lossy().to_string())
+        };
+        let targets = self.targets();
+
+        for change in self.changes.iter() {
+            let current = installed.get(&change.name);
+            match (change.action, &change.old_version, current) {
+                (ChangeAction::Reinstalled, _, _) => {}
+                (ChangeAction::Removed, _, Some(version)) => {
+                    transaction.notes.push(format!("{} was installed again since, it stays at {}", change.name, version))
+                }
+                (_, _, None) if change.action != ChangeAction::Removed => {
+                    transaction.notes.push(format!("{} was removed since, there is nothing to revert", change.name))
+                }
+                (_, _, Some(version)) if change.new_version.as_ref() != Some(version) => transaction.notes.push(format!(
+                    "{} changed to {} since, it stays there",
+                    change.name, version
+                )),
+                (ChangeAction::Installed, _, _) => transaction.remove.push(change.name.clone()),
+                (_, None, _) => {}
+                (action, Some(old), _) => {
+                    match cached(&change.name, old) {
+                        Some(path) => transaction.install_files.push(path),
+                        None if action == ChangeAction::Removed => {
+                            transaction.notes.push(format!(
+                                "{} {} is not cached, the repository version will be installed",
+                                change.name, old
+                            ));
+                            transaction.install.push(change.name.clone());
+                        }
+                        None => {
+                            transaction.notes.push(format!(
+                                "{} {} is not cached, it will stay at {}",
+                                change.name,
+                                old,
+                                change.new_version.clone().unwrap_or_default()
+                            ));
+                            continue;
+                        }
+                    }
+                    if action == ChangeAction::Removed && targets.as_ref().is_some_and(|t| !t.contains(&change.name)) {
+                        transaction.notes.push(format!("{} is restored as a dependency", change.name));
+                        transaction.dependencies.push(change.name.clone());
+                    }
+                }
+            }
+        }
+
+        transaction
+    }
+}
+
+impl HistoryFilter {
+    pub fn matches(&self, entry: &HistoryEntry) -> bool {
+        (self.package.is_empty() || entry.changes.iter().any(|c| c.name.contains(&self.package)))
+            && (self.from.is_empty() || entry.date() >= self.from.as_str())
+            && (self.to.is_empty() || entry.date() <= self.to.as_str())
+    }
+}
+
+//Splits "[timestamp] [SOURCE] message"
+fn split_line(line: &str) -> Option<(&str, &str, &str)> {
+    let (timestamp, rest) = line.strip_prefix('[')?.split_once("] ")?;
+    let (source, message) = match rest.strip_prefix('[') {
+        Some(rest) => rest.split_once("] ")?,
+        None => ("", rest),
+    };
+    Some((timestamp, source, message))
+}
+
+//"upgraded vim (9.0-1 -> 9.1-1)"
+fn parse_change(message: &str) -> Option<PackageChange> {
+    let (action, rest) = message.split_once(' ')?;
+    let action = match action {
+        "installed" => ChangeAction::Installed,
+        "upgraded" => ChangeAction::Upgraded,
+        "downgraded" => ChangeAction::Downgraded,
+        "reinstalled" => ChangeAction::Reinstalled,
+        "removed" => ChangeAction::Removed,
+        _ => return None,
+    };
+    let (name, versions) = rest.split_once(" (")?;
+    let versions = versions.strip_suffix(')')?;
+    let (old_version, new_version) = match versions.split_once(" -> ") {
+        Some((old, new)) => (Some(old.to_string()), Some(new.to_string())),
+        None if action == ChangeAction::Removed => (Some(versions.to_string()), None),
+        None if action == ChangeAction::Reinstalled => (Some(versions.to_string()), Some(versions.to_string())),
+        None => (None, Some(versions.to_string())),
+    };
+    Some(PackageChange {
+        name: name.to_string(),
+        action,
+        old_version,
+        new_version,
+    })
+}
+
+//Groups the log into transactions, oldest first. Runs that changed nothing (like a plain -Sy) are dropped
+pub fn parse(log: &str) -> Vec<HistoryEntry> {
+    let mut entries = vec![];
+    let mut current: Option<HistoryEntry> = None;
+
+    let flush = |entries: &mut Vec<HistoryEntry>, current: &mut Option<HistoryEntry>| {
+        if let Some(entry) = current.take()
+            && (!entry.changes.is_empty() || !entry.warnings.is_empty())
+        {
+            entries.push(entry);
+        }
+    };
+
+    for line in log.lines() {
+        let Some((timestamp, source, message)) = split_line(line) else {
+            continue;
+        };
+        if source == "PACMAN"
+            && let Some(command) = message.strip_prefix("Running '").and_then(|m| m.strip_suffix('\''))
+        {
+            flush(&mut entries, &mut current);
+            current = Some(HistoryEntry {
+                timestamp: timestamp.to_string(),
+                command: Some(command.to_string()),
+                ..Default::default()
+            });
+            continue;
+        }
+        if source != "ALPM" {
+            continue;
+        }
+        //A transaction without a command line of its own, or a second one in the same run
+        if message == "transaction started" && current.as_ref().is_none_or(|c| !c.changes.is_empty()) {
+            flush(&mut entries, &mut current);
+            current = Some(HistoryEntry {
+                timestamp: timestamp.to_string(),
+                ..Default::default()
+            });
+            continue;
+        }
+        let entry = current.get_or_insert_with(|| HistoryEntry {
+            timestamp: timestamp.to_string(),
+            ..Default::default()
+        });
+        if let Some(warning) = message.strip_prefix("warning: ") {
+            entry.warnings.push(warning.to_string());
+        } else if let Some(change) = parse_change(message) {
+            entry.changes.push(change);
+        }
+    }
+    flush(&mut entries, &mut current);
+
+    entries
+}
+
+pub fn load(path: &Path) -> Result<Vec<HistoryEntry>, String> {
+    std::fs::read_to_string(path)
+        .map(|log| parse(&log))
+        .map_err(|e| format!("Could not read {}: {}", path.to_string_lossy(), e))
+}
+
+//The LogFile set in pacman.conf, or pacman's default
+pub fn log_path(conf: &Path) -> PathBuf {
+    pacman_conf::options(conf, "LogFile")
+        .into_iter()
+        .next()
+        .map(PathBuf::from)
+        .unwrap_or(PathBuf::from(LOG_PATH))
+}
+
+#[test]
+fn test_history() {
+    let log = "\
+[2024-05-01T10:00:00+0200] [PACMAN] Running 'pacman -Sy'
+[2024-05-01T10:00:01+0200] [PACMAN] synchronizing package lists
+[2024-05-01T10:01:00+0200] [PACMAN] Running 'pacman -Syu'
+[2024-05-01T10:01:05+0200] [ALPM] transaction started
+[2024-05-01T10:01:06+0200] [ALPM] upgraded vim (9.0-1 -> 9.1-1)
+[2024-05-01T10:01:06+0200] [ALPM] installed vim-runtime (9.1-1)
+[2024-05-01T10:01:06+0200] [ALPM] warning: /etc/vimrc installed as /etc/vimrc.pacnew
+[2024-05-01T10:01:07+0200] [ALPM-SCRIPTLET] some output
+[2024-05-01T10:01:07+0200] [ALPM] transaction completed
+[2024-06-02T09:00:00+0200] [ALPM] transaction started
+[2024-06-02T09:00:00+0200] [ALPM] removed nano (8.0-1)
+[2024-06-02T09:00:00+0200] [ALPM] downgraded vim (9.1-1 -> 9.0-1)
+[2024-06-02T09:00:00+0200] [ALPM] transaction completed
+";
+    let entries = parse(log);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].command.as_deref(), Some("pacman -Syu"));
+    assert_eq!(entries[0].warnings, vec!["/etc/vimrc installed as /etc/vimrc.pacnew".to_string()]);
+    assert_eq!(
+        entries[0].changes[0],
+        PackageChange {
+            name: "vim".to_string(),
+            action: ChangeAction::Upgraded,
+            old_version: Some("9.0-1".to_string()),
+            new_version: Some("9.1-1".to_string()),
+        }
+    );
+    assert_eq!(entries[1].command, None);
+    assert_eq!(entries[1].change_for("nano").unwrap().describe(), "removed nano 8.0-1");
+
+    let filter = HistoryFilter {
+        package: "vim".to_string(),
+        from: "2024-06-01".to_string(),
+        to: String::new(),
+    };
+    assert_eq!(entries.iter().filter(|e| filter.matches(e)).count(), 1);
+
+    let cache = vec![CacheGroup {
+        name: "vim".to_string(),
+        versions: vec![crate::logic::cache::CachedPackage {
+            name: "vim".to_string(),
+            version: "9.0-1".to_string(),
+            arch: "x86_64".to_string(),
+            path: PathBuf::from("/cache/vim-9.0-1-x86_64.pkg.tar.zst"),
+            size: 0,
+        }],
+    }];
+    let installed = |list: &[(&str, &str)]| list.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect::<HashMap<_, _>>();
+    let undo = entries[0].undo(&cache, &installed(&[("vim", "9.1-1"), ("vim-runtime", "9.1-1")]));
+    assert_eq!(undo.remove, vec!["vim-runtime".to_string()]);
+    assert_eq!(undo.install_files, vec!["/cache/vim-9.0-1-x86_64.pkg.tar.zst".to_string()]);
+    //vim 9.1 still needs vim-runtime, so it is only removed once vim is downgraded
+    assert_eq!(
+        undo.commands(),
+        vec![
+            "pacman -U --noconfirm '/cache/vim-9.0-1-x86_64.pkg.tar.zst'".to_string(),
+            "pacman -R --noconfirm 'vim-runtime'".to_string()
+        ]
+    );
+    let undo = entries[1].undo(&cache, &installed(&[("vim", "9.0-1")]));
+    assert_eq!(undo.install, vec!["nano".to_string()]);
+    assert!(undo.install_files.is_empty());
+    assert!(undo.dependencies.is_empty());
+    assert_eq!(undo.notes.len(), 2);
+
+    //vim-runtime was removed and vim upgraded again after the first transaction
+    let undo = entries[0].undo(&cache, &installed(&[("vim", "9.2-1")]));
+    assert!(undo.is_empty());
+    assert_eq!(undo.notes.len(), 2);
+
+    let removal = parse(
+        "\
+[2024-07-01T09:00:00+0200] [PACMAN] Running 'pacman -Rs nano'
+[2024-07-01T09:00:00+0200] [ALPM] transaction started
+[2024-07-01T09:00:00+0200] [ALPM] removed nano (8.0-1)
+[2024-07-01T09:00:00+0200] [ALPM] removed nano-syntax (1.0-1)
+[2024-07-01T09:00:00+0200] [ALPM] removed vim (9.0-1)
+",
+    );
+    let undo = removal[0].undo(&cache, &installed(&[("vim", "9.1-1")]));
+    assert_eq!(undo.install, vec!["nano".to_string(), "nano-syntax".to_string()]);
+    assert_eq!(undo.dependencies, vec!["nano-syntax".to_string()]);
+    assert!(undo.install_files.is_empty());
+}
@@ -0,0 +1,170 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::logic::pacman_conf::{self, PacmanConf};
+use crate::logic::transaction::shell_quote;
+
+//Where a hold is stored: IgnorePkg applies to every pacman frontend, the app list only to this app
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HoldScope {
+    IgnorePkg,
+    App,
+}
+
+//App level hold list, one package name per line
+pub fn hold_file() -> PathBuf {
+    let config = std::env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(std::env::var("HOME").unwrap_or_default()).join(".config"));
+    config.join("pacmanager").join("holds")
+}
+
+pub fn load(path: &Path) -> BTreeSet<String> {
+    std::fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .map(|l| l.split('#').next().unwrap_or_default().trim())
+        .filter(|l| !l.is_empty())
+        .map(|l| l.to_string())
+        .collect()
+}
+
+pub fn save(path: &Path, holds: &BTreeSet<String>) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let contents = holds.iter().map(|h| format!("{}\n", h)).collect::<String>();
+    std::fs::write(path, contents).map_err(|e| e.to_string())
+}
+
+//IgnorePkg accepts shell globs matched with fnmatch: `*`, `?` and bracket sets such as `[0-9]` or `[!a]`
+pub fn pattern_matches(pattern: &str, name: &str) -> bool {
+    glob(&pattern.chars().collect::<Vec<_>>(), &name.chars().collect::<Vec<_>>())
+}
+
+fn glob(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|i| glob(&pattern[1..], &name[i..])),
+        Some('?') => !name.is_empty() && glob(&pattern[1..], &name[1..]),
+        Some('[') => match (bracket(&pattern[1..], name.first()), name.first()) {
+            (Some((matched, rest)), Some(_)) => matched && glob(rest, &name[1..]),
+            //An unclosed bracket is a literal "["
+            (None, Some('[')) => glob(&pattern[1..], &name[1..]),
+            _ => false,
+        },
+        Some(c) => name.first() == Some(c) && glob(&pattern[1..], &name[1..]),
+    }
+}
+
+//Whether `c` is in the set starting right after "[", with the pattern left after the closing "]"
+fn bracket<'a>(set: &'a [char], c: Option<&char>) -> Option<(bool, &'a [char])> {
+    let negated = matches!(set.first(), Some('!') | Some('^'));
+    let mut i = usize::from(negated);
+    let mut matched = false;
+    //A "]" right after the opening bracket is part of the set
+    let mut first = true;
+    while i < set.len() {
+        if set[i] == ']' && !first {
+            return Some((matched != negated, &set[i + 1..]));
+        }
+        first = false;
+        if i + 2 < set.len() && set[i + 1] == '-' && set[i + 2] != ']' {
+            matched |= c.is_some_and(|c| (set[i]..=set[i + 2]).contains(c));
+            i += 3;
+        } else {
+            matched |= c == Some(&set[i]);
+            i += 1;
+        }
+    }
+    None
+}
+
+//Which list holds `name`, IgnorePkg wins when it is in both
+pub fn scope_of(conf: &Path, holds: &Path, name: &str) -> Option<HoldScope> {
+    if pacman_conf::options(conf, "IgnorePkg").iter().any(|p| pattern_matches(p, name)) {
+        return Some(HoldScope::IgnorePkg);
+    }
+    load(holds).contains(name).then_some(HoldScope::App)
+}
+
+//Holds `name`, or releases it from whichever list holds it. Returns stderr like the other operations
+pub fn set_held(conf: &Path, holds: &Path, name: &str, held: bool, scope: HoldScope) -> String {
+    let names = [name.to_string()];
+    if held && scope == HoldScope::IgnorePkg {
+        let mut parsed = match PacmanConf::load(conf) {
+            Ok(c) => c,
+            Err(e) => return e,
+        };
+        parsed.add_values("IgnorePkg", &names);
+        return parsed.save(conf);
+    }
+
+    let mut list = load(holds);
+    if held {
+        list.insert(name.to_string());
+        return save(holds, &list).err().unwrap_or_default();
+    }
+    if list.remove(name)
+        && let Err(e) = save(holds, &list)
+    {
+        return e;
+    }
+    let ignored = pacman_conf::options(conf, "IgnorePkg");
+    if ignored.contains(&name.to_string()) {
+        let mut parsed = match PacmanConf::load(conf) {
+            Ok(c) => c,
+            Err(e) => return e,
+        };
+        parsed.remove_values("IgnorePkg", &names);
+        return parsed.save(conf);
+    }
+    //Dropping a pattern would release every package it matches, that is left to the pacman.conf editor
+    if let Some(pattern) = ignored.iter().find(|p| pattern_matches(p, name)) {
+        return format!(
+            "{} is held by the IgnorePkg pattern \"{}\", remove or narrow the pattern in pacman.conf to release it",
+            name, pattern
+        );
+    }
+    String::new()
+}
+
+//Extra arguments for `pacman -Syu` so app level holds are skipped like IgnorePkg ones
+pub fn ignore_args(holds: &BTreeSet<String>) -> String {
+    if holds.is_empty() {
+        return String::new();
+    }
+    format!(" --ignore {}", shell_quote(&holds.iter().cloned().collect::<Vec<_>>().join(",")))
+}
+
+#[test]
+fn test_hold() {
+    assert!(pattern_matches("linux*", "linux-lts"));
+    assert!(pattern_matches("*-git", "neovim-git"));
+    assert!(!pattern_matches("linux", "linux-lts"));
+    assert!(pattern_matches("python?", "python3"));
+    assert!(!pattern_matches("python?", "python"));
+    assert!(pattern_matches("linux[0-9]*", "linux6-rt"));
+    assert!(!pattern_matches("linux[!0-9]*", "linux6-rt"));
+    assert!(pattern_matches("lib[]x]", "lib]"));
+    assert!(pattern_matches("a[b", "a[b"));
+
+    let root = std::env::temp_dir().join(format!("pacmanager-hold-{}", std::process::id()));
+    let holds = root.join("holds");
+    let conf = root.join("pacman.conf");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(&conf, "[options]\nIgnorePkg = linux*\n").unwrap();
+
+    assert_eq!(scope_of(&conf, &holds, "linux-lts"), Some(HoldScope::IgnorePkg));
+    assert_eq!(scope_of(&conf, &holds, "vim"), None);
+    assert_eq!(set_held(&conf, &holds, "vim", true, HoldScope::App), "");
+    assert_eq!(scope_of(&conf, &holds, "vim"), Some(HoldScope::App));
+    assert_eq!(ignore_args(&load(&holds)), " --ignore 'vim'");
+    assert_eq!(set_held(&conf, &holds, "vim", false, HoldScope::App), "");
+    assert!(load(&holds).is_empty());
+    let error = set_held(&conf, &holds, "linux-lts", false, HoldScope::IgnorePkg);
+    assert!(error.contains("\"linux*\""));
+    assert_eq!(scope_of(&conf, &holds, "linux-lts"), Some(HoldScope::IgnorePkg));
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
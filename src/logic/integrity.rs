@@ -0,0 +1,106 @@
+use std::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind {
+    Missing,
+    //Checksum, size, permission, ownership or mtime mismatches
+    Altered,
+    //Mismatches on files listed in the package's backup array, usually expected local edits
+    Backup,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegrityIssue {
+    pub package: String,
+    pub path: String,
+    pub kind: IssueKind,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntegrityReport {
+    pub issues: Vec<IntegrityIssue>,
+    pub packages_checked: usize,
+}
+
+impl IntegrityReport {
+    pub fn of_kind(&self, kind: IssueKind) -> Vec<&IntegrityIssue> {
+        self.issues.iter().filter(|i| i.kind == kind).collect()
+    }
+}
+
+//Splits "pkg: /path (reason)" into its parts
+fn parse_issue(line: &str) -> Option<(String, String, String)> {
+    let (package, rest) = line.split_once(": ")?;
+    let open = rest.rfind(" (")?;
+    let path = rest[..open].trim().to_string();
+    let reason = rest[open + 2..].trim_end_matches(")").to_string();
+    Some((package.trim().to_string(), path, reason))
+}
+
+//Parses the combined stdout and stderr of `pacman -Qk` or `pacman -Qkk`
+pub fn parse_check(output: &str) -> IntegrityReport {
+    let mut report = IntegrityReport::default();
+
+    for line in output.lines() {
+        let line = line.trim();
+        if line.contains(" total files, ") {
+            report.packages_checked += 1;
+        } else if let Some(rest) = line.strip_prefix("backup file: ")
+            && let Some((package, path, reason)) = parse_issue(rest)
+        {
+            report.issues.push(IntegrityIssue { package, path, kind: IssueKind::Backup, reason });
+        } else if let Some(rest) = line.strip_prefix("warning: ")
+            && let Some((package, path, reason)) = parse_issue(rest)
+        {
+            let kind = if reason.contains("No such file") {
+                IssueKind::Missing
+            } else {
+                IssueKind::Altered
+            };
+            report.issues.push(IntegrityIssue { package, path, kind, reason });
+        }
+    }
+
+    report
+}
+
+//Runs the check for one package, or for every installed package when `package` is `None`.
+//`thorough` uses -Qkk which also compares checksums, permissions and modification times
+pub fn check(package: Option<String>, thorough: bool) -> Result<IntegrityReport, String> {
+    let output = Command::new("pacman")
+        .arg(if thorough { "-Qkk" } else { "-Qk" })
+        .args(package)
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    //pacman exits with 1 whenever an issue is found, so the status is not an error by itself
+    let combined = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    if combined.contains("error: package") {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+    Ok(parse_check(&combined))
+}
+
+#[test]
+fn test_parse_check() {
+    let report = parse_check(
+        "warning: vim: /usr/share/vim/vim91/doc/tags (No such file or directory)
+warning: vim: /usr/bin/xxd (SHA256 checksum mismatch)
+warning: vim: /usr/bin/vim (Permissions mismatch)
+backup file: vim: /etc/vimrc (Modification time mismatch)
+vim: 2170 total files, 1 altered file
+bash: 150 total files, 0 altered files",
+    );
+
+    assert_eq!(report.packages_checked, 2);
+    assert_eq!(report.of_kind(IssueKind::Missing).len(), 1);
+    assert_eq!(report.of_kind(IssueKind::Altered).len(), 2);
+    let backup = report.of_kind(IssueKind::Backup);
+    assert_eq!(backup[0].path, "/etc/vimrc");
+    assert_eq!(backup[0].reason, "Modification time mismatch");
+}
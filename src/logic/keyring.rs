@@ -0,0 +1,203 @@
+use std::process::Command;
+
+use crate::logic::privileged;
+use crate::logic::transaction::shell_quote;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Key {
+    pub fingerprint: String,
+    //"rsa4096", "ed25519"...
+    pub algorithm: String,
+    pub created: String,
+    //Set when the key expires, "expired" or "revoked" tell whether that already happened
+    pub expires: Option<String>,
+    pub expired: bool,
+    pub revoked: bool,
+    //User ids with the validity gpg computed for them, like ("full", "Name <mail>")
+    pub uids: Vec<(String, String)>,
+}
+
+impl Key {
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.fingerprint.to_lowercase().contains(&query) || self.uids.iter().any(|(_, uid)| uid.to_lowercase().contains(&query))
+    }
+}
+
+//Parses the human readable listing `pacman-key --list-keys` prints
+pub fn parse_keys(output: &str) -> Vec<Key> {
+    let mut keys: Vec<Key> = vec![];
+
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("pub ") {
+            //"   rsa4096 2011-09-23 [SC] [expires: 2025-01-01]"
+            let mut key = Key::default();
+            let mut words = rest.split_whitespace();
+            key.algorithm = words.next().unwrap_or_default().to_string();
+            key.created = words.next().unwrap_or_default().to_string();
+            let rest = words.collect::<Vec<_>>().join(" ");
+            for (marker, expired, revoked) in [("[expires: ", false, false), ("[expired: ", true, false), ("[revoked: ", false, true)] {
+                if let Some(date) = rest.split(marker).nth(1).and_then(|d| d.split(']').next()) {
+                    if !revoked {
+                        key.expires = Some(date.to_string());
+                    }
+                    key.expired |= expired;
+                    key.revoked |= revoked;
+                }
+            }
+            keys.push(key);
+        } else if let Some(rest) = line.strip_prefix("uid ") {
+            let rest = rest.trim();
+            let (validity, uid) = match rest.strip_prefix('[').and_then(|r| r.split_once(']')) {
+                Some((validity, uid)) => (validity.trim().to_string(), uid.trim().to_string()),
+                None => (String::new(), rest.to_string()),
+            };
+            if let Some(key) = keys.last_mut() {
+                key.uids.push((validity, uid));
+            }
+        } else if let Some(key) = keys.last_mut()
+            && key.fingerprint.is_empty()
+            && line.starts_with(' ')
+            && !line.trim().is_empty()
+            && line.trim().chars().all(|c| c.is_ascii_hexdigit())
+        {
+            key.fingerprint = line.trim().to_string();
+        }
+    }
+
+    keys
+}
+
+pub fn list_keys() -> Result<Vec<Key>, String> {
+    let output = Command::new("pacman-key")
+        .arg("--list-keys")
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(with_hint(String::from_utf8_lossy(&output.stderr).to_string()));
+    }
+    Ok(parse_keys(&String::from_utf8_lossy(&output.stdout)))
+}
+
+//Keys whose user ids carry the e-mail of a "Packager" field such as "Name <mail@example.org>"
+pub fn packager_keys<'a>(keys: &'a [Key], packager: &str) -> Vec<&'a Key> {
+    let Some(mail) = packager.split('<').nth(1).and_then(|m| m.split('>').next()) else {
+        return vec![];
+    };
+    keys.iter().filter(|k| k.uids.iter().any(|(_, uid)| uid.contains(&format!("<{}>", mail)))).collect()
+}
+
+//The maintenance actions are judged by their exit status, gpg reports progress such as "checking the trustdb" on stderr
+pub fn refresh() -> (bool, String) {
+    privileged::run_status("pacman-key --refresh-keys".to_string())
+}
+
+//Re-imports the keys shipped by the keyring packages, such as archlinux-keyring
+pub fn populate() -> (bool, String) {
+    privileged::run_status("pacman-key --populate".to_string())
+}
+
+//Locally signs a key of the keyring so packages signed with it are trusted
+pub fn lsign(fingerprint: &str) -> (bool, String) {
+    privileged::run_status(format!("pacman-key --lsign-key {}", shell_quote(fingerprint)))
+}
+
+//Fetches a key missing from the keyring and locally signs it
+pub fn import(key: &str) -> (bool, String) {
+    privileged::run_status(format!(
+        "pacman-key --recv-keys {0} && pacman-key --lsign-key {0}",
+        shell_quote(key)
+    ))
+}
+
+//Key pacman could not find in the keyring, from errors like `key "ABCD..." is unknown`
+pub fn missing_key(stderror: &str) -> Option<String> {
+    stderror.lines().find_map(|line| {
+        let rest = line.split("key \"").nth(1)?;
+        let (key, rest) = rest.split_once('"')?;
+        (rest.trim_start().starts_with("is unknown") && key.chars().all(|c| c.is_ascii_hexdigit()))
+            .then(|| key.to_string())
+    })
+}
+
+//What to do about the signature errors pacman and pacman-key report, the specific causes are checked before the generic error
+pub fn hint(stderror: &str) -> Option<&'static str> {
+    let hints = [
+        (
+            "unknown trust",
+            "The packager's key is not trusted locally. Populate the keyring, or locally sign the key in the Keyring tab",
+        ),
+        (
+            "key is disabled",
+            "The packager's key was disabled. Populate the keyring to restore the keys shipped with archlinux-keyring",
+        ),
+        (
+            "required key missing from keyring",
+            "The signing key is not in the keyring. Populate the keyring, or import and sign the key in the Keyring tab",
+        ),
+        (
+            "expired",
+            "A signing key has expired. Refresh the keys in the Keyring tab to fetch its new expiry date",
+        ),
+        (
+            "keyserver",
+            "The keyserver could not be reached. Check the network connection or the keyserver in /etc/pacman.d/gnupg/gpg.conf",
+        ),
+        (
+            "no secret key available",
+            "The keyring is not initialized. Run `pacman-key --init` and populate the keyring",
+        ),
+        (
+            "invalid or corrupted package (PGP signature)",
+            "The package signature could not be verified. Install the latest archlinux-keyring first, then retry. If it keeps failing, refresh the keys in the Keyring tab",
+        ),
+    ];
+    hints.into_iter().find(|(pattern, _)| stderror.contains(pattern)).map(|(_, hint)| hint)
+}
+
+//Appends the hint for a signature error to stderr, other errors are returned as they are
+pub fn with_hint(stderror: String) -> String {
+    match hint(&stderror) {
+        Some(hint) => format!("{}\n\nHint: {}", stderror.trim_end(), hint),
+        None => stderror,
+    }
+}
+
+#[test]
+fn test_keyring() {
+    let output = "\
+/etc/pacman.d/gnupg/pubring.gpg
+-------------------------------
+pub   rsa4096 2011-09-23 [SC]
+      647F28654894E3BD457199BE38DBBDC86092693E
+uid           [  full  ] Jan Alexander Steffens (heftig) <heftig@archlinux.org>
+sub   rsa4096 2011-09-23 [E]
+
+pub   ed25519 2020-01-01 [SC] [expired: 2022-01-01]
+      0123456789ABCDEF0123456789ABCDEF01234567
+uid           [ expired] Someone <someone@example.org>
+uid           [ expired] Someone Else <else@example.org>
+";
+    let keys = parse_keys(output);
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys[0].fingerprint, "647F28654894E3BD457199BE38DBBDC86092693E");
+    assert_eq!(keys[0].uids[0].0, "full");
+    assert!(!keys[0].expired);
+    assert!(keys[1].expired);
+    assert_eq!(keys[1].expires.as_deref(), Some("2022-01-01"));
+    assert_eq!(keys[1].uids.len(), 2);
+    assert!(keys[1].matches("else@"));
+
+    let signers = packager_keys(&keys, "Jan Alexander Steffens (heftig) <heftig@archlinux.org>");
+    assert_eq!(signers.len(), 1);
+    assert!(packager_keys(&keys, "Unknown Packager").is_empty());
+
+    let error = "error: vim: signature from \"Someone\" is unknown trust\n".to_string();
+    assert!(with_hint(error).contains("Hint: The packager's key is not trusted locally"));
+    assert_eq!(with_hint("error: target not found: foo".to_string()), "error: target not found: foo");
+    assert_eq!(hint("gpg: checking the trustdb\ngpg: no ultimately trusted keys found\n"), None);
+
+    let error = "error: vim: key \"0123456789ABCDEF\" is unknown\nerror: required key missing from keyring\n";
+    assert_eq!(missing_key(error).as_deref(), Some("0123456789ABCDEF"));
+    assert_eq!(missing_key("error: vim: signature from \"Someone\" is unknown trust"), None);
+}
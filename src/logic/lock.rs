@@ -0,0 +1,134 @@
+use std::path::{Path, PathBuf};
+
+use crate::logic::files::DB_PATH;
+use crate::logic::privileged;
+use crate::logic::transaction::shell_quote;
+
+pub const PROC_PATH: &str = "/proc";
+
+//Processes that take the database lock, pacman itself covers yay, paru and the like
+const LOCKERS: [&str; 3] = ["pacman", "pamac-daemon", "packagekitd"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Holder {
+    pub pid: u32,
+    pub command: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LockState {
+    Free,
+    //The lock exists and one of these processes is probably holding it
+    Held(Vec<Holder>),
+    //The lock exists but nothing that could hold it is running, usually left by a crash
+    Stale,
+}
+
+pub fn lock_file() -> PathBuf {
+    Path::new(DB_PATH).join("db.lck")
+}
+
+//Running processes that could hold the lock, read from `proc_root` like /proc
+pub fn holders(proc_root: &Path) -> Vec<Holder> {
+    let Ok(entries) = std::fs::read_dir(proc_root) else {
+        return vec![];
+    };
+    let mut holders = entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_string_lossy().parse::<u32>().ok()?;
+            let comm = std::fs::read_to_string(entry.path().join("comm")).ok()?;
+            if !LOCKERS.contains(&comm.trim()) {
+                return None;
+            }
+            //cmdline is NUL separated, fall back to the bare name when it cannot be read
+            let command = std::fs::read(entry.path().join("cmdline"))
+                .map(|c| String::from_utf8_lossy(&c).split('\0').filter(|x| !x.is_empty()).collect::<Vec<_>>().join(" "))
+                .ok()
+                .filter(|c| !c.is_empty())
+                .unwrap_or(comm.trim().to_string());
+            Some(Holder { pid, command })
+        })
+        .collect::<Vec<_>>();
+    holders.sort_by_key(|h| h.pid);
+    holders
+}
+
+pub fn check(lock: &Path, proc_root: &Path) -> LockState {
+    if !lock.exists() {
+        return LockState::Free;
+    }
+    let holders = holders(proc_root);
+    if holders.is_empty() { LockState::Stale } else { LockState::Held(holders) }
+}
+
+impl LockState {
+    pub fn describe(&self) -> String {
+        match self {
+            LockState::Free => "The package database is not locked".to_string(),
+            LockState::Held(holders) => format!(
+                "The package database is locked by {}",
+                holders
+                    .iter()
+                    .map(|h| format!("\"{}\" (pid {})", h.command, h.pid))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            LockState::Stale => "The package database is locked but no package manager is running, the lock is probably stale".to_string(),
+        }
+    }
+}
+
+//Script that removes the lock unless something that could hold it is running. The check runs as root next to the removal,
+//so a pacman started while the password prompt was open is still noticed
+pub fn remove_script(lock: &Path) -> String {
+    format!(
+        "for p in {}; do if pgrep -x \"$p\" >/dev/null; then echo \"$p is running, the lock is not removed\" >&2; exit 1; fi; done; rm -f {}",
+        LOCKERS.map(shell_quote).join(" "),
+        shell_quote(&lock.to_string_lossy())
+    )
+}
+
+//Removes the lock as root, refusing when anything that could hold it is running
+pub fn remove_stale(lock: &Path, proc_root: &Path) -> String {
+    match check(lock, proc_root) {
+        LockState::Free => String::new(),
+        LockState::Stale => privileged::run(remove_script(lock)),
+        held => format!("{}, it is not removed while that is running", held.describe()),
+    }
+}
+
+#[test]
+fn test_lock() {
+    let root = std::env::temp_dir().join(format!("pacmanager-lock-{}", std::process::id()));
+    let proc_root = root.join("proc");
+    let lock = root.join("db.lck");
+    std::fs::create_dir_all(proc_root.join("1")).unwrap();
+    std::fs::write(proc_root.join("1").join("comm"), "systemd\n").unwrap();
+
+    assert_eq!(check(&lock, &proc_root), LockState::Free);
+    std::fs::write(&lock, "").unwrap();
+    assert_eq!(check(&lock, &proc_root), LockState::Stale);
+
+    std::fs::create_dir_all(proc_root.join("4242")).unwrap();
+    std::fs::write(proc_root.join("4242").join("comm"), "pacman\n").unwrap();
+    std::fs::write(proc_root.join("4242").join("cmdline"), "pacman\0-Syu\0").unwrap();
+    let state = check(&lock, &proc_root);
+    assert_eq!(
+        state,
+        LockState::Held(vec![Holder {
+            pid: 4242,
+            command: "pacman -Syu".to_string(),
+        }])
+    );
+    assert!(state.describe().contains("\"pacman -Syu\" (pid 4242)"));
+    assert!(remove_stale(&lock, &proc_root).contains("not removed"));
+    assert!(lock.exists());
+
+    //The script itself only needs root for the real lock
+    let status = std::process::Command::new("sh").arg("-c").arg(remove_script(&lock)).status().unwrap();
+    assert!(status.success());
+    assert!(!lock.exists());
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
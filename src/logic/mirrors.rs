@@ -0,0 +1,329 @@
+use std::fmt::Debug;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::logic::privileged;
+
+pub const MIRRORLIST: &str = "/etc/pacman.d/mirrorlist";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mirror {
+    //Server URL with the $repo and $arch placeholders left in
+    pub url: String,
+    //From the "## Country" heading above it, empty when there is none
+    pub country: String,
+    pub enabled: bool,
+    //Other comment lines found above the server, such as reflector's "# With:" block, written back above it
+    pub comments: Vec<String>,
+}
+
+//The mirrorlist split into its leading comment block and the servers, in priority order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mirrorlist {
+    pub header: Vec<String>,
+    pub mirrors: Vec<Mirror>,
+    //Comment lines after the last server
+    pub footer: Vec<String>,
+}
+
+//Mirrors probed at the same time, each probe mostly waits on the network
+const PROBE_WORKERS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProbeResult {
+    //Time until the first byte arrived
+    pub latency: Duration,
+    //Bytes per second over the whole download
+    pub throughput: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RankBy {
+    #[default]
+    Latency,
+    Throughput,
+}
+
+//How mirrors are measured, a trait so tests can point it at a local server
+pub trait MirrorProbe: Debug + Send + Sync {
+    fn probe(&self, url: &str) -> Result<ProbeResult, String>;
+}
+
+//Downloads the file with curl and reads the timings curl reports
+#[derive(Debug, Clone)]
+pub struct CurlProbe {
+    pub timeout: u64,
+}
+
+impl Default for CurlProbe {
+    fn default() -> Self {
+        CurlProbe { timeout: 10 }
+    }
+}
+
+impl MirrorProbe for CurlProbe {
+    fn probe(&self, url: &str) -> Result<ProbeResult, String> {
+        let output = Command::new("curl")
+            .args(["-fsS", "-o", "/dev/null", "--max-time", &self.timeout.to_string()])
+            .args(["-w", "%{time_starttransfer} %{speed_download}"])
+            .arg(url)
+            .output()
+            .map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(format!("{}: {}", url, String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let mut parts = stdout.split_whitespace().map(|x| x.replace(',', ".").parse::<f64>());
+        match (parts.next(), parts.next()) {
+            (Some(Ok(latency)), Some(Ok(throughput))) => Ok(ProbeResult {
+                latency: Duration::from_secs_f64(latency),
+                throughput,
+            }),
+            _ => Err(format!("{}: unexpected curl output \"{}\"", url, stdout)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Prober(pub Arc<dyn MirrorProbe>);
+
+impl Default for Prober {
+    fn default() -> Self {
+        Prober(Arc::new(CurlProbe::default()))
+    }
+}
+
+impl Mirror {
+    //The core database of the mirror, small enough to probe every mirror quickly
+    pub fn probe_url(&self, arch: &str) -> String {
+        format!("{}/core.db", self.url.replace("$repo", "core").replace("$arch", arch))
+    }
+}
+
+impl Mirrorlist {
+    pub fn parse(content: &str) -> Self {
+        let mut list = Mirrorlist::default();
+        let mut country = String::new();
+        //The comment block at the top, up to the first blank line, is the generator's own header
+        let mut in_header = true;
+        //Lines that are neither servers nor country headings, kept for the next server
+        let mut comments: Vec<String> = vec![];
+
+        for line in content.lines() {
+            let trimmed = line.trim();
+            let (body, enabled) = match trimmed.strip_prefix('#') {
+                Some(rest) => (rest.trim(), false),
+                None => (trimmed, true),
+            };
+            let server = body
+                .split_once('=')
+                .filter(|(key, _)| key.trim() == "Server")
+                .map(|(_, value)| value.trim().to_string());
+
+            if in_header {
+                if trimmed.starts_with('#') && server.is_none() {
+                    list.header.push(line.to_string());
+                    continue;
+                }
+                in_header = false;
+            }
+            if let Some(url) = server {
+                list.mirrors.push(Mirror {
+                    url,
+                    country: country.clone(),
+                    enabled,
+                    comments: std::mem::take(&mut comments),
+                });
+            } else if let Some(heading) = trimmed.strip_prefix("##")
+                && !heading.trim().is_empty()
+            {
+                country = heading.trim().to_string();
+            } else if !trimmed.is_empty() || !comments.is_empty() {
+                //Blank lines are only kept inside a comment block, the ones between sections are written by `render`
+                comments.push(line.to_string());
+            }
+        }
+        while comments.last().is_some_and(|l| l.trim().is_empty()) {
+            comments.pop();
+        }
+        list.footer = comments;
+
+        list
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        std::fs::read_to_string(path)
+            .map(|c| Self::parse(&c))
+            .map_err(|e| format!("Could not read {}: {}", path.to_string_lossy(), e))
+    }
+
+    //Writes the servers in their current order, repeating the country heading whenever it changes.
+    //Comments stay with the server below them and go above its heading
+    pub fn render(&self) -> String {
+        let lines = |lines: &[String]| lines.iter().map(|l| format!("{}\n", l)).collect::<String>();
+        let mut out = lines(&self.header);
+        let mut country = None;
+        for mirror in self.mirrors.iter() {
+            if country != Some(&mirror.country) {
+                if !out.is_empty() {
+                    out += "\n";
+                }
+                out += &lines(&mirror.comments);
+                if !mirror.country.is_empty() {
+                    out += &format!("## {}\n", mirror.country);
+                }
+                country = Some(&mirror.country);
+            } else {
+                out += &lines(&mirror.comments);
+            }
+            out += &format!("{}Server = {}\n", if mirror.enabled { "" } else { "#" }, mirror.url);
+        }
+        if !self.footer.is_empty() {
+            out += "\n";
+            out += &lines(&self.footer);
+        }
+        out
+    }
+
+    //Moves a mirror one place up (towards higher priority) or down
+    pub fn move_mirror(&mut self, index: usize, up: bool) {
+        let target = if up { index.checked_sub(1) } else { Some(index + 1) };
+        if let Some(target) = target
+            && target < self.mirrors.len()
+            && index < self.mirrors.len()
+        {
+            self.mirrors.swap(index, target);
+        }
+    }
+
+    //Writes the mirrorlist as root, keeping the previous version as .bak
+    pub fn save(&self, path: &Path) -> String {
+        if !self.mirrors.iter().any(|m| m.enabled) {
+            return "At least one mirror has to be enabled".to_string();
+        }
+        privileged::write_file(path.to_string_lossy().to_string(), self.render(), true)
+    }
+}
+
+//Probes every mirror, PROBE_WORKERS at a time, the fastest ones first and the unreachable ones last
+pub fn rank(mirrors: &[Mirror], probe: &dyn MirrorProbe, arch: &str, by: RankBy) -> Vec<(Mirror, Result<ProbeResult, String>)> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(vec![]);
+    std::thread::scope(|scope| {
+        for _ in 0..PROBE_WORKERS.min(mirrors.len()) {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(mirror) = mirrors.get(index) else {
+                        break;
+                    };
+                    let result = probe.probe(&mirror.probe_url(arch));
+                    results.lock().unwrap().push((index, mirror.clone(), result));
+                }
+            });
+        }
+    });
+    //Back in list order first, so mirrors that rank the same keep their relative order
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _, _)| *index);
+    let mut results = results.into_iter().map(|(_, m, r)| (m, r)).collect::<Vec<_>>();
+    results.sort_by(|(_, a), (_, b)| match (a, b) {
+        (Ok(a), Ok(b)) => match by {
+            RankBy::Latency => a.latency.cmp(&b.latency),
+            RankBy::Throughput => b.throughput.total_cmp(&a.throughput),
+        },
+        (Ok(_), Err(_)) => std::cmp::Ordering::Less,
+        (Err(_), Ok(_)) => std::cmp::Ordering::Greater,
+        (Err(_), Err(_)) => std::cmp::Ordering::Equal,
+    });
+    results
+}
+
+#[test]
+fn test_mirrors() {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    let content = "\
+##
+## Arch Linux repository mirrorlist
+##
+
+## Worldwide
+#Server = https://geo.mirror.pkgbuild.com/$repo/os/$arch
+
+## Germany
+Server = https://mirror.example.de/archlinux/$repo/os/$arch
+#Server = https://other.example.de/$repo/os/$arch
+";
+    let mut list = Mirrorlist::parse(content);
+    assert_eq!(list.header.len(), 3);
+    assert_eq!(list.mirrors.len(), 3);
+    assert_eq!(list.mirrors[0].country, "Worldwide");
+    assert!(!list.mirrors[0].enabled);
+    assert!(list.mirrors[1].enabled);
+    assert_eq!(list.render(), content);
+
+    let reflector = "\
+################################################################################
+################# Arch Linux mirrorlist generated by Reflector #################
+################################################################################
+
+# With:       reflector --latest 5
+# When:       2024-05-01 10:00:00 UTC
+
+Server = https://a.example.org/$repo/os/$arch
+# Added by hand
+Server = https://b.example.org/$repo/os/$arch
+
+# End of list
+";
+    let parsed = Mirrorlist::parse(reflector);
+    assert_eq!(parsed.mirrors[0].comments.len(), 3);
+    assert_eq!(parsed.mirrors[1].comments, vec!["# Added by hand".to_string()]);
+    assert_eq!(parsed.footer, vec!["# End of list".to_string()]);
+    assert_eq!(parsed.render(), reflector);
+
+    list.move_mirror(1, true);
+    assert_eq!(list.mirrors[0].country, "Germany");
+    assert!(list.render().contains("## Germany\nServer = https://mirror.example.de/archlinux/$repo/os/$arch\n\n## Worldwide\n"));
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().take(2) {
+            let mut stream = stream.unwrap();
+            let mut request = [0; 1024];
+            let len = stream.read(&mut request).unwrap();
+            let request = String::from_utf8_lossy(&request[..len]).to_string();
+            let response = if request.starts_with("GET /fast/core/os/x86_64/core.db") {
+                "HTTP/1.1 200 OK\r\nContent-Length: 4\r\nConnection: close\r\n\r\ndata".to_string()
+            } else {
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+            };
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+
+    let mirrors = vec![
+        Mirror {
+            url: format!("http://127.0.0.1:{}/missing/$repo/os/$arch", port),
+            country: String::new(),
+            enabled: true,
+            comments: vec![],
+        },
+        Mirror {
+            url: format!("http://127.0.0.1:{}/fast/$repo/os/$arch", port),
+            country: String::new(),
+            enabled: true,
+            comments: vec![],
+        },
+    ];
+    let ranked = rank(&mirrors, &CurlProbe::default(), "x86_64", RankBy::Latency);
+    assert_eq!(ranked[0].0, mirrors[1]);
+    assert!(ranked[0].1.is_ok());
+    assert!(ranked[1].1.is_err());
+}
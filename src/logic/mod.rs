@@ -1,2 +1,22 @@
+pub mod aur;
+pub mod cache;
+pub mod export;
+pub mod files;
+pub mod filter;
+pub mod history;
+pub mod hold;
+pub mod integrity;
+pub mod keyring;
+pub mod lock;
+pub mod mirrors;
 pub mod package;
+pub mod pacman_conf;
+pub mod pacnew;
+pub mod privileged;
+pub mod profile;
+pub mod prompts;
+pub mod reconcile;
 pub mod server;
+pub mod snapshot;
+pub mod transaction;
+pub mod version;
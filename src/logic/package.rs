@@ -19,6 +19,60 @@ impl Package {
         self.properties.insert(prop, value);
     }
 
+    //Every field sorted by name
+    pub fn get_properties(&self) -> Vec<(String, String)> {
+        let mut properties = self.properties.clone().into_iter().collect::<Vec<_>>();
+        properties.sort();
+        properties
+    }
+
+    pub fn remove_property(&mut self, prop: String) {
+        self.properties.remove(&prop);
+    }
+
+    //Splits a whitespace separated property such as "Groups" or "Licenses", pacman uses "None" for empty lists
+    pub fn get_list_property(&self, prop: String) -> Vec<String> {
+        self.get_property(prop)
+            .unwrap_or_default()
+            .split_whitespace()
+            .filter(|x| *x != "None")
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    //"repo/name" when the repository is known, which pins pacman to that repository
+    pub fn get_qualified_name(&self) -> String {
+        let name = self.get_property("Name".to_string()).unwrap_or_default();
+        match self.get_property("Repository".to_string()) {
+            Some(repo) => format!("{}/{}", repo, name),
+            None => name,
+        }
+    }
+
+    pub fn is_installed(&self) -> bool {
+        self.get_property("Installed".to_string()).unwrap_or_default() == "True"
+    }
+
+    //Set by `Server::check_installed` for packages that no sync database provides
+    pub fn is_foreign(&self) -> bool {
+        self.get_property("Foreign".to_string()).unwrap_or_default() == "True"
+    }
+
+    //Held in IgnorePkg or in the app hold list, skipped by upgrades
+    pub fn is_held(&self) -> bool {
+        self.get_property("Held".to_string()).unwrap_or_default() == "True"
+    }
+
+    //Set for AUR search results and for foreign packages found in the AUR
+    pub fn is_aur(&self) -> bool {
+        self.get_property("AUR".to_string()).unwrap_or_default() == "True"
+    }
+
+    //Copies every property of `other` into this package, overwriting the ones present in both
+    pub fn merge(&mut self, other: Package) {
+        self.properties.extend(other.properties);
+    }
+
     pub fn from_raw(data: Vec<String>) -> Package {
         let mut curr_prop = "".to_string();
         let mut curr_val = "".to_string();
@@ -38,6 +92,9 @@ impl Package {
                 curr_val += line.trim();
             }
         }
+        if !curr_prop.is_empty() {
+            props.insert(curr_prop, curr_val);
+        }
 
         return Package {
             properties: props,
@@ -45,6 +102,72 @@ impl Package {
         };
     }
 
+    //Builds a package from the `.PKGINFO` of a package archive, using the same property names as `pacman -Qi`
+    pub fn from_pkginfo(data: String) -> Package {
+        let mut props: HashMap<String, String> = HashMap::new();
+
+        for line in data.lines() {
+            let line = line.trim();
+            if line.starts_with("#") {
+                continue;
+            }
+            let Some((key, value)) = line.split_once("=") else {
+                continue;
+            };
+            let prop = match key.trim() {
+                "pkgname" => "Name",
+                "pkgver" => "Version",
+                "pkgdesc" => "Description",
+                "url" => "URL",
+                "arch" => "Architecture",
+                "packager" => "Packager",
+                "license" => "Licenses",
+                "group" => "Groups",
+                "depend" => "Depends On",
+                "optdepend" => "Optional Deps",
+                "provides" => "Provides",
+                "conflict" => "Conflicts With",
+                "replaces" => "Replaces",
+                "size" => "Installed Size",
+                _ => continue,
+            };
+            let mut value = value.trim().to_string();
+            if prop == "Installed Size" {
+                value = format!("{:.2} KiB", value.parse::<f64>().unwrap_or_default() / 1024.0);
+            }
+            //Multi-valued keys are repeated once per value
+            props
+                .entry(prop.to_string())
+                .and_modify(|v| {
+                    v.push_str("  ");
+                    v.push_str(&value)
+                })
+                .or_insert(value);
+        }
+
+        Package { properties: props }
+    }
+
+    //Reads the metadata of a local `.pkg.tar.*` archive, bsdtar ships with libarchive which pacman depends on
+    pub fn from_archive(path: String) -> Result<Package, String> {
+        let output = Command::new("bsdtar")
+            .arg("-xOf")
+            .arg(&path)
+            .arg(".PKGINFO")
+            .output()
+            .map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(format!(
+                "Could not read {}: {}",
+                path,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        let mut package = Self::from_pkginfo(String::from_utf8_lossy(&output.stdout).to_string());
+        package.set_property("Archive".to_string(), path);
+        Ok(package)
+    }
+
     pub fn sync_all(&mut self) {
         if self.get_property("Installed".to_string()).unwrap() != "True".to_string() {
             return;
@@ -56,34 +179,11 @@ impl Package {
             .unwrap()
             .stdout).unwrap().split("\n").map(|x| x.to_string()).collect::<Vec<String>>();
         let updated = Self::from_raw(raw_lines);
-        self.properties = updated.properties;
+        //Merging keeps sync-only fields such as "Repository"
+        self.merge(updated);
         self.sync_installed();
     }
 
-    pub fn install_or_update(name: String) -> String {
-        println!("Attempting to update or install {}", name);
-
-        let payload = format!("pkexec pacman -Syy {} --noconfirm", name);
-
-        let command = std::process::Command::new("sh")
-            .arg("-c")
-            .arg(payload)
-            .output();
-        return String::from_utf8(command.unwrap().stderr).unwrap()
-    }
-
-    pub fn uninstall(name: String) -> String {
-        println!("Attempting to uninstall {}", name);
-
-        let payload = format!("pkexec pacman -R {} --noconfirm", name);
-
-        let command = std::process::Command::new("sh")
-            .arg("-c")
-            .arg(payload)
-            .output();
-        return String::from_utf8(command.unwrap().stderr).unwrap()
-    }
-
     //Makes a call to the OS package manager to sync the in-memory package with the real one
     pub fn sync_installed(&mut self) {
         let name = self.get_property("Name".to_string()).unwrap_or_default();
@@ -148,3 +248,29 @@ impl Package {
         return naive_dt;
     }
 }
+
+#[test]
+fn test_from_pkginfo() {
+    let package = Package::from_pkginfo(
+        "# Generated by makepkg 6.1.0
+pkgname = internal-tool
+pkgver = 1.2.0-3
+pkgdesc = Tool = helper
+arch = x86_64
+size = 2048
+license = MIT
+depend = glibc
+depend = openssl>=3
+"
+        .to_string(),
+    );
+
+    assert_eq!(package.get_property("Name".to_string()), Some("internal-tool".to_string()));
+    assert_eq!(package.get_property("Version".to_string()), Some("1.2.0-3".to_string()));
+    assert_eq!(package.get_property("Description".to_string()), Some("Tool = helper".to_string()));
+    assert_eq!(package.get_property("Installed Size".to_string()), Some("2.00 KiB".to_string()));
+    assert_eq!(
+        package.get_list_property("Depends On".to_string()),
+        vec!["glibc".to_string(), "openssl>=3".to_string()]
+    );
+}
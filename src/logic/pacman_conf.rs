@@ -0,0 +1,463 @@
+use std::fmt::Display;
+use std::path::Path;
+
+use crate::logic::privileged;
+
+pub const PACMAN_CONF: &str = "/etc/pacman.conf";
+
+//Options that take no value, a commented out one of these is a disabled directive rather than prose
+const FLAGS: [&str; 8] = [
+    "Color",
+    "CheckSpace",
+    "VerbosePkgLists",
+    "ILoveCandy",
+    "DisableDownloadTimeout",
+    "UseSyslog",
+    "NoProgressBar",
+    "DisableSandbox",
+];
+
+const SIG_LEVELS: [&str; 5] = ["Never", "Optional", "Required", "TrustedOnly", "TrustAll"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineKind {
+    //Comments, blank lines and anything else, kept verbatim
+    Other,
+    Section { name: String },
+    //`value` is None for flags such as Color
+    Directive { key: String, value: Option<String> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfLine {
+    pub raw: String,
+    pub kind: LineKind,
+    //False for sections and directives commented out with '#'
+    pub enabled: bool,
+    //Section the line belongs to, empty before the first header
+    pub section: String,
+}
+
+//pacman.conf as a list of lines, so edits only rewrite the lines they touch and comments and ordering survive
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PacmanConf {
+    pub lines: Vec<ConfLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repository {
+    pub name: String,
+    pub enabled: bool,
+    pub includes: Vec<String>,
+    pub servers: Vec<String>,
+    pub sig_level: Option<String>,
+}
+
+fn parse_line(raw: &str) -> (LineKind, bool) {
+    let trimmed = raw.trim();
+    let (body, enabled) = match trimmed.strip_prefix('#') {
+        Some(rest) => (rest.trim(), false),
+        None => (trimmed, true),
+    };
+    let body = body.split('#').next().unwrap_or_default().trim();
+
+    if body.len() > 2 && body.starts_with('[') && body.ends_with(']') {
+        let name = body[1..body.len() - 1].to_string();
+        return (LineKind::Section { name }, enabled);
+    }
+
+    let (key, value) = match body.split_once('=') {
+        Some((k, v)) => (k.trim(), Some(v.trim().to_string())),
+        None => (body, None),
+    };
+    let is_key = !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric());
+    if is_key && (enabled || value.is_some() || FLAGS.contains(&key)) {
+        let key = key.to_string();
+        return (LineKind::Directive { key, value }, enabled);
+    }
+    (LineKind::Other, true)
+}
+
+impl PacmanConf {
+    pub fn parse(content: &str) -> Self {
+        let mut section = String::new();
+        let lines = content
+            .lines()
+            .map(|raw| {
+                let (kind, enabled) = parse_line(raw);
+                if let LineKind::Section { name } = &kind {
+                    section = name.clone();
+                }
+                ConfLine {
+                    raw: raw.to_string(),
+                    kind,
+                    enabled,
+                    section: section.clone(),
+                }
+            })
+            .collect();
+        PacmanConf { lines }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        std::fs::read_to_string(path)
+            .map(|c| Self::parse(&c))
+            .map_err(|e| format!("Could not read {}: {}", path.to_string_lossy(), e))
+    }
+
+    fn directives<'a>(&'a self, section: &'a str, key: &'a str) -> impl Iterator<Item = (usize, &'a ConfLine)> + 'a {
+        self.lines.iter().enumerate().filter(move |(_, l)| {
+            l.section == section && matches!(&l.kind, LineKind::Directive { key: k, .. } if k == key)
+        })
+    }
+
+    //Every value of `key` in `section`, repeated and multi-valued keys are flattened
+    pub fn values(&self, section: &str, key: &str) -> Vec<String> {
+        self.directives(section, key)
+            .filter(|(_, l)| l.enabled)
+            .flat_map(|(_, l)| match &l.kind {
+                LineKind::Directive { value: Some(v), .. } => v.split_whitespace().map(|x| x.to_string()).collect(),
+                _ => vec![],
+            })
+            .collect()
+    }
+
+    //Value of an enabled [options] entry, flags are Some("")
+    pub fn option(&self, key: &str) -> Option<String> {
+        self.directives("options", key)
+            .find(|(_, l)| l.enabled)
+            .map(|(_, l)| match &l.kind {
+                LineKind::Directive { value, .. } => value.clone().unwrap_or_default(),
+                _ => String::new(),
+            })
+    }
+
+    //Rewrites a directive line, keeping the alignment of "Key   = value" when it had one
+    fn set_line(&mut self, index: usize, value: Option<String>, enabled: bool) {
+        let line = &mut self.lines[index];
+        let LineKind::Directive { key, .. } = &line.kind else {
+            return;
+        };
+        let key = key.clone();
+        let prefix = if enabled { "" } else { "#" };
+        line.raw = match &value {
+            None => format!("{}{}", prefix, key),
+            Some(v) => {
+                let lhs = line
+                    .raw
+                    .trim_start()
+                    .trim_start_matches('#')
+                    .trim_start()
+                    .split_once('=')
+                    .map(|(l, _)| l.to_string())
+                    .unwrap_or(format!("{:<11} ", key));
+                format!("{}{}= {}", prefix, lhs, v).trim_end().to_string()
+            }
+        };
+        line.kind = LineKind::Directive { key, value };
+        line.enabled = enabled;
+    }
+
+    //Sets an [options] entry, enabling a commented out one if possible. None comments it out
+    pub fn set_option(&mut self, key: &str, value: Option<String>) {
+        let existing = self.directives("options", key).map(|(i, l)| (i, l.enabled)).collect::<Vec<_>>();
+
+        let Some(value) = value else {
+            for (index, enabled) in existing {
+                if enabled {
+                    let value = match &self.lines[index].kind {
+                        LineKind::Directive { value, .. } => value.clone(),
+                        _ => None,
+                    };
+                    self.set_line(index, value, false);
+                }
+            }
+            return;
+        };
+        let value = (!FLAGS.contains(&key)).then_some(value);
+
+        let target = existing.iter().find(|(_, e)| *e).or(existing.first()).map(|(i, _)| *i);
+        match target {
+            Some(index) => {
+                self.set_line(index, value, true);
+                //Repeated lines would add to the value, their contents now live in the first one
+                for (other, enabled) in existing {
+                    if other != index && enabled {
+                        let value = match &self.lines[other].kind {
+                            LineKind::Directive { value, .. } => value.clone(),
+                            _ => None,
+                        };
+                        self.set_line(other, value, false);
+                    }
+                }
+            }
+            None => {
+                let header = self.lines.iter().position(|l| l.enabled && l.kind == LineKind::Section { name: "options".to_string() });
+                let index = match header {
+                    Some(header) => {
+                        //After the last directive of the section, so it lands before the comments of the next one
+                        let mut index = header + 1;
+                        for (i, l) in self.lines.iter().enumerate().skip(header + 1) {
+                            if l.section != "options" {
+                                break;
+                            }
+                            if matches!(l.kind, LineKind::Directive { .. }) {
+                                index = i + 1;
+                            }
+                        }
+                        index
+                    }
+                    None => {
+                        self.lines.insert(
+                            0,
+                            ConfLine {
+                                raw: "[options]".to_string(),
+                                kind: LineKind::Section { name: "options".to_string() },
+                                enabled: true,
+                                section: "options".to_string(),
+                            },
+                        );
+                        1
+                    }
+                };
+                self.lines.insert(
+                    index,
+                    ConfLine {
+                        raw: String::new(),
+                        kind: LineKind::Directive { key: key.to_string(), value: None },
+                        enabled: true,
+                        section: "options".to_string(),
+                    },
+                );
+                self.set_line(index, value, true);
+            }
+        }
+    }
+
+    //Appends `names` to a multi-valued [options] entry such as IgnorePkg, skipping the ones already there
+    pub fn add_values(&mut self, key: &str, names: &[String]) {
+        let mut values = self.values("options", key);
+        for name in names {
+            if !values.contains(name) {
+                values.push(name.clone());
+            }
+        }
+        self.set_option(key, Some(values.join(" ")));
+    }
+
+    //Drops `names` from a multi-valued [options] entry, commenting it out once it is empty
+    pub fn remove_values(&mut self, key: &str, names: &[String]) {
+        let values = self
+            .values("options", key)
+            .into_iter()
+            .filter(|v| !names.contains(v))
+            .collect::<Vec<_>>();
+        self.set_option(key, (!values.is_empty()).then_some(values.join(" ")));
+    }
+
+    //Repository sections in file order, commented out ones included
+    pub fn repositories(&self) -> Vec<Repository> {
+        let mut repositories: Vec<Repository> = vec![];
+        for line in self.lines.iter() {
+            match &line.kind {
+                LineKind::Section { name } if name != "options" => repositories.push(Repository {
+                    name: name.clone(),
+                    enabled: line.enabled,
+                    includes: vec![],
+                    servers: vec![],
+                    sig_level: None,
+                }),
+                LineKind::Directive { key, value: Some(value) } if line.section != "options" => {
+                    let Some(repository) = repositories.last_mut().filter(|r| r.name == line.section) else {
+                        continue;
+                    };
+                    //Disabled lines of an enabled repository are not part of it
+                    if repository.enabled && !line.enabled {
+                        continue;
+                    }
+                    match key.as_str() {
+                        "Include" => repository.includes.push(value.clone()),
+                        "Server" => repository.servers.push(value.clone()),
+                        "SigLevel" => repository.sig_level = Some(value.clone()),
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+        repositories
+    }
+
+    //Uncomments or comments out a repository header together with its directives
+    pub fn set_repository_enabled(&mut self, name: &str, enabled: bool) {
+        for index in 0..self.lines.len() {
+            let line = &self.lines[index];
+            if line.section != name || line.enabled == enabled {
+                continue;
+            }
+            match line.kind.clone() {
+                LineKind::Section { .. } => {
+                    let line = &mut self.lines[index];
+                    line.raw = format!("{}[{}]", if enabled { "" } else { "#" }, name);
+                    line.enabled = enabled;
+                }
+                LineKind::Directive { value, .. } => self.set_line(index, value, enabled),
+                LineKind::Other => {}
+            }
+        }
+    }
+
+    //The checks pacman would fail on, so a broken file is never written
+    pub fn validate(&self) -> Result<(), String> {
+        let mut problems = vec![];
+
+        if !self.lines.iter().any(|l| l.enabled && l.kind == LineKind::Section { name: "options".to_string() }) {
+            problems.push("There is no [options] section".to_string());
+        }
+        if self.lines.iter().any(|l| l.enabled && l.section.is_empty() && matches!(l.kind, LineKind::Directive { .. })) {
+            problems.push("There are options before the first section".to_string());
+        }
+        if let Some(parallel) = self.option("ParallelDownloads")
+            && !parallel.parse::<u32>().is_ok_and(|p| p > 0)
+        {
+            problems.push(format!("ParallelDownloads must be a positive number, not \"{}\"", parallel));
+        }
+
+        let mut seen = vec![];
+        for repository in self.repositories().into_iter().filter(|r| r.enabled) {
+            if seen.contains(&repository.name) {
+                problems.push(format!("[{}] is defined more than once", repository.name));
+            }
+            if repository.includes.is_empty() && repository.servers.is_empty() {
+                problems.push(format!("[{}] has no Include or Server", repository.name));
+            }
+            seen.push(repository.name);
+        }
+
+        let sig_levels = self
+            .lines
+            .iter()
+            .filter(|l| l.enabled)
+            .filter_map(|l| match &l.kind {
+                LineKind::Directive { key, value: Some(v) } if key.ends_with("SigLevel") => Some(v.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for level in sig_levels.iter().flat_map(|l| l.split_whitespace()) {
+            let bare = level.trim_start_matches("Package").trim_start_matches("Database");
+            if !SIG_LEVELS.contains(&bare) {
+                problems.push(format!("Unknown SigLevel \"{}\"", level));
+            }
+        }
+
+        if problems.is_empty() { Ok(()) } else { Err(problems.join("\n")) }
+    }
+
+    //Validates and writes the file as root, the previous version is kept as .bak
+    pub fn save(&self, path: &Path) -> String {
+        if let Err(e) = self.validate() {
+            return e;
+        }
+        privileged::write_file(path.to_string_lossy().to_string(), self.to_string(), true)
+    }
+}
+
+impl Display for PacmanConf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in self.lines.iter() {
+            writeln!(f, "{}", line.raw)?;
+        }
+        Ok(())
+    }
+}
+
+//Values of `key` in the [options] section, multi-valued keys are split on whitespace
+pub fn options(conf: &Path, key: &str) -> Vec<String> {
+    PacmanConf::load(conf).map(|c| c.values("options", key)).unwrap_or_default()
+}
+
+//Package cache directories, pacman falls back to /var/cache/pacman/pkg/ when none is configured
+pub fn cache_dirs(conf: &Path) -> Vec<String> {
+    let dirs = options(conf, "CacheDir");
+    if dirs.is_empty() {
+        return vec!["/var/cache/pacman/pkg/".to_string()];
+    }
+    dirs
+}
+
+#[test]
+fn test_add_ignored() {
+    let conf = "[options]\n#IgnorePkg   =\nHoldPkg = pacman\n[core]\nInclude = /etc/pacman.d/mirrorlist\n";
+    let add_ignored = |content: &str, names: &[String]| {
+        let mut conf = PacmanConf::parse(content);
+        conf.add_values("IgnorePkg", names);
+        conf.to_string()
+    };
+    let once = add_ignored(conf, &["vim".to_string()]);
+    assert_eq!(once, "[options]\nIgnorePkg   = vim\nHoldPkg = pacman\n[core]\nInclude = /etc/pacman.d/mirrorlist\n");
+    let twice = add_ignored(&once, &["vim".to_string(), "nano".to_string()]);
+    assert!(twice.starts_with("[options]\nIgnorePkg   = vim nano\n"));
+    assert_eq!(add_ignored("[options]\n", &["vim".to_string()]), "[options]\nIgnorePkg   = vim\n");
+
+    let mut parsed = PacmanConf::parse(&twice);
+    parsed.remove_values("IgnorePkg", &["vim".to_string()]);
+    assert_eq!(parsed.values("options", "IgnorePkg"), vec!["nano".to_string()]);
+    parsed.remove_values("IgnorePkg", &["nano".to_string()]);
+    assert!(parsed.to_string().starts_with("[options]\n#IgnorePkg   = nano\n"));
+}
+
+#[test]
+fn test_pacman_conf() {
+    let content = "\
+# General options
+[options]
+HoldPkg     = pacman glibc
+#Color
+ParallelDownloads = 5
+SigLevel    = Required DatabaseOptional
+
+# The testing repositories are disabled by default
+#[core-testing]
+#Include = /etc/pacman.d/mirrorlist
+
+[core]
+Include = /etc/pacman.d/mirrorlist
+
+#[multilib]
+#Include = /etc/pacman.d/mirrorlist
+";
+    let mut conf = PacmanConf::parse(content);
+    assert_eq!(conf.to_string(), content);
+    assert_eq!(conf.values("options", "HoldPkg"), vec!["pacman".to_string(), "glibc".to_string()]);
+    assert_eq!(conf.option("Color"), None);
+    assert_eq!(conf.option("ParallelDownloads"), Some("5".to_string()));
+    assert!(conf.validate().is_ok());
+
+    let repositories = conf.repositories();
+    assert_eq!(repositories.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), vec!["core-testing", "core", "multilib"]);
+    assert!(!repositories[2].enabled);
+    assert_eq!(repositories[2].includes, vec!["/etc/pacman.d/mirrorlist".to_string()]);
+
+    conf.set_option("Color", Some(String::new()));
+    conf.set_option("ParallelDownloads", Some("10".to_string()));
+    conf.set_option("HoldPkg", None);
+    conf.set_repository_enabled("multilib", true);
+    let edited = conf.to_string();
+    assert!(edited.contains("\nColor\nParallelDownloads = 10\n"));
+    assert!(edited.contains("\n#HoldPkg     = pacman glibc\n"));
+    assert!(edited.ends_with("[multilib]\nInclude = /etc/pacman.d/mirrorlist\n"));
+    assert!(edited.starts_with("# General options\n"));
+    assert!(PacmanConf::parse(&edited).repositories()[2].enabled);
+
+    conf.set_option("ParallelDownloads", Some("many".to_string()));
+    conf.set_repository_enabled("core", false);
+    conf.lines.push(ConfLine {
+        raw: "[broken]".to_string(),
+        kind: LineKind::Section { name: "broken".to_string() },
+        enabled: true,
+        section: "broken".to_string(),
+    });
+    let problems = conf.validate().unwrap_err();
+    assert!(problems.contains("ParallelDownloads"));
+    assert!(problems.contains("[broken] has no Include or Server"));
+}
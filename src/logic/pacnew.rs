@@ -0,0 +1,195 @@
+use std::path::{Path, PathBuf};
+
+use crate::logic::files::owners_in_db;
+use crate::logic::privileged;
+use crate::logic::transaction::shell_quote;
+
+pub const CONFIG_ROOT: &str = "/etc";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigKind {
+    //New default shipped by an upgrade, the live file was modified locally
+    Pacnew,
+    //Local file saved aside when its package was removed or the default changed
+    Pacsave,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+    pub live: PathBuf,
+    pub kind: ConfigKind,
+    pub owner: Option<String>,
+}
+
+//A run of identical lines, or a place where the live file and the new one disagree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Hunk {
+    Same(Vec<String>),
+    Changed { live: Vec<String>, new: Vec<String> },
+}
+
+//Finds every .pacnew and .pacsave below `root` and maps it to the package owning the live file,
+//the local database is read once for all of them
+pub fn scan(root: &Path, db_path: &Path) -> Vec<ConfigFile> {
+    let mut found = vec![];
+    let mut pending = vec![root.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let kind = match path.extension().and_then(|e| e.to_str()) {
+                Some("pacnew") => ConfigKind::Pacnew,
+                Some("pacsave") => ConfigKind::Pacsave,
+                _ => continue,
+            };
+            let live = path.with_extension("");
+            found.push(ConfigFile { path, live, kind, owner: None });
+        }
+    }
+
+    let mut owners = owners_in_db(db_path, &found.iter().map(|f| f.live.clone()).collect::<Vec<_>>());
+    for file in found.iter_mut() {
+        file.owner = owners.remove(&file.live);
+    }
+    found.sort_by(|a, b| a.path.cmp(&b.path));
+    found
+}
+
+//Line based diff from the longest common subsequence, config files are small enough for the quadratic table
+pub fn diff(live: &str, new: &str) -> Vec<Hunk> {
+    let a = live.lines().collect::<Vec<_>>();
+    let b = new.lines().collect::<Vec<_>>();
+
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut hunks: Vec<Hunk> = vec![];
+    let push_same = |hunks: &mut Vec<Hunk>, line: &str| match hunks.last_mut() {
+        Some(Hunk::Same(lines)) => lines.push(line.to_string()),
+        _ => hunks.push(Hunk::Same(vec![line.to_string()])),
+    };
+    let push_changed = |hunks: &mut Vec<Hunk>, live_line: Option<&str>, new_line: Option<&str>| {
+        if !matches!(hunks.last(), Some(Hunk::Changed { .. })) {
+            hunks.push(Hunk::Changed { live: vec![], new: vec![] });
+        }
+        if let Some(Hunk::Changed { live, new }) = hunks.last_mut() {
+            live.extend(live_line.map(|x| x.to_string()));
+            new.extend(new_line.map(|x| x.to_string()));
+        }
+    };
+
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            push_same(&mut hunks, a[i]);
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i >= a.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            push_changed(&mut hunks, None, Some(b[j]));
+            j += 1;
+        } else {
+            push_changed(&mut hunks, Some(a[i]), None);
+            i += 1;
+        }
+    }
+
+    hunks
+}
+
+//Rebuilds a file from the hunks, taking the new side of the n-th changed hunk when `use_new[n]` is set
+pub fn merge(hunks: &[Hunk], use_new: &[bool]) -> String {
+    let mut lines: Vec<&String> = vec![];
+    let mut changed = 0;
+    for hunk in hunks {
+        match hunk {
+            Hunk::Same(same) => lines.extend(same),
+            Hunk::Changed { live, new } => {
+                lines.extend(if use_new.get(changed).cloned().unwrap_or(false) { new } else { live });
+                changed += 1;
+            }
+        }
+    }
+    lines.into_iter().map(|l| format!("{}\n", l)).collect()
+}
+
+impl ConfigFile {
+    pub fn read(&self) -> (String, String) {
+        (
+            std::fs::read_to_string(&self.live).unwrap_or_default(),
+            std::fs::read_to_string(&self.path).unwrap_or_default(),
+        )
+    }
+
+    //Keeps the live file and discards the .pacnew/.pacsave
+    pub fn keep(&self) -> String {
+        privileged::run(format!("rm -f {}", shell_quote(&self.path.to_string_lossy())))
+    }
+
+    //Moves the .pacnew/.pacsave over the live file
+    pub fn replace(&self) -> String {
+        privileged::run(format!(
+            "mv -f {} {}",
+            shell_quote(&self.path.to_string_lossy()),
+            shell_quote(&self.live.to_string_lossy())
+        ))
+    }
+
+    //Writes the merged contents to the live file, then discards the .pacnew/.pacsave
+    pub fn merge(&self, contents: String) -> String {
+        let stderror = privileged::write_file(self.live.to_string_lossy().to_string(), contents, false);
+        if !stderror.is_empty() {
+            return stderror;
+        }
+        self.keep()
+    }
+}
+
+#[test]
+fn test_pacnew() {
+    let root = std::env::temp_dir().join(format!("pacmanager-pacnew-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("pacman.d")).unwrap();
+    std::fs::write(root.join("pacman.conf"), "a\nb\nc\n").unwrap();
+    std::fs::write(root.join("pacman.conf.pacnew"), "a\nB\nc\nd\n").unwrap();
+    std::fs::write(root.join("pacman.d").join("mirrorlist.pacsave"), "x\n").unwrap();
+
+    let found = scan(&root, Path::new("/nonexistent"));
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].kind, ConfigKind::Pacnew);
+    assert_eq!(found[0].live, root.join("pacman.conf"));
+    assert_eq!(found[1].kind, ConfigKind::Pacsave);
+
+    let (live, new) = found[0].read();
+    let hunks = diff(&live, &new);
+    assert_eq!(
+        hunks,
+        vec![
+            Hunk::Same(vec!["a".to_string()]),
+            Hunk::Changed { live: vec!["b".to_string()], new: vec!["B".to_string()] },
+            Hunk::Same(vec!["c".to_string()]),
+            Hunk::Changed { live: vec![], new: vec!["d".to_string()] },
+        ]
+    );
+    assert_eq!(merge(&hunks, &[false, true]), "a\nb\nc\nd\n");
+    assert_eq!(merge(&hunks, &[true, false]), "a\nB\nc\n");
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
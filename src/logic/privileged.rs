@@ -0,0 +1,50 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use crate::logic::transaction::shell_quote;
+
+//Runs a shell script as root through pkexec and returns its stderr, like the package operations
+pub fn run(script: String) -> String {
+    run_status(script).1
+}
+
+//Like `run`, also telling whether the script succeeded since pacman writes warnings to stderr on success
+pub fn run_status(script: String) -> (bool, String) {
+    let payload = format!("pkexec sh -c {}", shell_quote(&script));
+
+    match Command::new("sh").arg("-c").arg(payload).output() {
+        Ok(output) => (output.status.success(), String::from_utf8_lossy(&output.stderr).to_string()),
+        Err(e) => (false, e.to_string()),
+    }
+}
+
+//Replaces a root owned file with `contents`. When `backup` is set the previous version is kept as "<path>.bak"
+pub fn write_file(path: String, contents: String, backup: bool) -> String {
+    let mut script = String::new();
+    if backup {
+        script += &format!("cp -a {0} {0}.bak && ", shell_quote(&path));
+    }
+    script += &format!("cat > {}", shell_quote(&path));
+
+    let child = Command::new("pkexec")
+        .args(["sh", "-c", &script])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(c) => c,
+        Err(e) => return e.to_string(),
+    };
+    if let Some(mut stdin) = child.stdin.take()
+        && let Err(e) = stdin.write_all(contents.as_bytes())
+    {
+        return e.to_string();
+    }
+    match child.wait_with_output() {
+        Ok(output) => String::from_utf8_lossy(&output.stderr).to_string(),
+        Err(e) => e.to_string(),
+    }
+}
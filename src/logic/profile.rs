@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::logic::hold;
+use crate::logic::package::Package;
+use crate::logic::pacman_conf::Repository;
+use crate::logic::reconcile::{self, WantedPackage};
+use crate::logic::transaction::Transaction;
+
+//The desired state of the system, kept as TOML next to the hold list
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    //Explicitly installed packages, their dependencies are left to pacman
+    pub packages: Vec<String>,
+    //IgnorePkg or app holds, patterns are compared as written
+    pub holds: Vec<String>,
+    //Repositories that must be enabled in pacman.conf
+    pub repositories: Vec<String>,
+    //Explicit packages missing from the profile are only reported unless this is set
+    pub remove_extra: bool,
+}
+
+//How the live system differs from the profile
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Drift {
+    pub missing: Vec<String>,
+    pub extra: Vec<String>,
+    pub unheld: Vec<String>,
+    //Commented out in pacman.conf, enabling them fixes the drift
+    pub disabled_repositories: Vec<String>,
+    //Not configured at all, they have to be added by hand
+    pub unknown_repositories: Vec<String>,
+}
+
+pub fn profile_file() -> PathBuf {
+    hold::hold_file().with_file_name("profile.toml")
+}
+
+pub fn parse(content: &str) -> Result<Profile, String> {
+    toml::from_str(content).map_err(|e| format!("Invalid profile: {}", e))
+}
+
+pub fn load(path: &Path) -> Result<Profile, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    parse(&content)
+}
+
+pub fn save(path: &Path, profile: &Profile) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let content = toml::to_string_pretty(profile).map_err(|e| e.to_string())?;
+    std::fs::write(path, content).map_err(|e| e.to_string())
+}
+
+//Sets `remove_extra` in the file as it is written, the comments and the order of a hand written profile are kept
+pub fn set_remove_extra(path: &Path, remove_extra: bool) -> Result<(), String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    let mut document = content.parse::<toml_edit::DocumentMut>().map_err(|e| format!("Invalid profile: {}", e))?;
+    document["remove_extra"] = toml_edit::value(remove_extra);
+    std::fs::write(path, document.to_string()).map_err(|e| e.to_string())
+}
+
+impl Profile {
+    //Captures the current system: explicit packages, every hold and the enabled repositories
+    pub fn from_system(packages: &[Package], held: &[String], repositories: &[Repository]) -> Profile {
+        let mut names = packages
+            .iter()
+            .filter(|p| p.is_installed())
+            .filter(|p| p.get_property("Install Reason".to_string()).unwrap_or_default().starts_with("Explicitly"))
+            .filter_map(|p| p.get_property("Name".to_string()))
+            .collect::<Vec<_>>();
+        names.sort();
+        Profile {
+            packages: names,
+            holds: held.to_vec(),
+            repositories: repositories.iter().filter(|r| r.enabled).map(|r| r.name.clone()).collect(),
+            remove_extra: false,
+        }
+    }
+
+    //`held` are the IgnorePkg patterns followed by the app holds
+    pub fn drift(&self, packages: &[Package], held: &[String], repositories: &[Repository]) -> Drift {
+        let wanted = self
+            .packages
+            .iter()
+            .map(|name| WantedPackage {
+                name: name.clone(),
+                version: None,
+            })
+            .collect::<Vec<_>>();
+        let reconciliation = reconcile::reconcile(&wanted, packages);
+        let mut drift = Drift {
+            missing: reconciliation.missing.into_iter().map(|w| w.name).collect(),
+            extra: reconciliation.extra,
+            unheld: self.holds.iter().filter(|h| !held.contains(h)).cloned().collect(),
+            ..Default::default()
+        };
+        for name in self.repositories.iter() {
+            match repositories.iter().find(|r| r.name == *name) {
+                Some(repository) if repository.enabled => {}
+                Some(_) => drift.disabled_repositories.push(name.clone()),
+                None => drift.unknown_repositories.push(name.clone()),
+            }
+        }
+        drift
+    }
+}
+
+impl Drift {
+    pub fn count(&self) -> usize {
+        self.missing.len() + self.extra.len() + self.unheld.len() + self.disabled_repositories.len() + self.unknown_repositories.len()
+    }
+
+    //Everything fixable in one go: repositories are enabled first, missing holds are written to IgnorePkg last
+    pub fn transaction(&self, profile: &Profile) -> Transaction {
+        let mut transaction = Transaction {
+            description: "Apply the system profile".to_string(),
+            install: self.missing.clone(),
+            ignore: self.unheld.clone(),
+            repositories: self.disabled_repositories.clone(),
+            ..Default::default()
+        };
+        transaction.notes.extend(self.disabled_repositories.iter().map(|r| format!("Enable the {} repository", r)));
+        transaction.notes.extend(self.unheld.iter().map(|h| format!("Hold {} through IgnorePkg", h)));
+        transaction.notes.extend(self.unknown_repositories.iter().map(|r| format!("{} is not configured in pacman.conf, add it by hand", r)));
+        if profile.remove_extra {
+            transaction.remove = self.extra.clone();
+        } else if !self.extra.is_empty() {
+            transaction.notes.push(format!("{} package(s) outside the profile are kept", self.extra.len()));
+        }
+        transaction
+    }
+}
+
+#[test]
+fn test_profile() {
+    let raw = |lines: &[&str]| Package::from_raw(lines.iter().map(|x| x.to_string()).collect());
+    let packages = vec![
+        raw(&["Name            : vim", "Installed       : True", "Install Reason  : Explicitly installed"]),
+        raw(&["Name            : gcc", "Installed       : True", "Install Reason  : Explicitly installed"]),
+        raw(&["Name            : git", "Installed       : False"]),
+    ];
+    let repository = |name: &str, enabled: bool| Repository {
+        name: name.to_string(),
+        enabled,
+        includes: vec![],
+        servers: vec![],
+        sig_level: None,
+    };
+    let repositories = vec![repository("core", true), repository("multilib", false)];
+
+    let profile = parse(
+        r#"
+packages = ["vim", "git"]
+holds = ["linux"]
+repositories = ["core", "multilib", "chaotic-aur"]
+"#,
+    )
+    .unwrap();
+    assert!(!profile.remove_extra);
+    assert!(parse("packages = 1").is_err());
+
+    let drift = profile.drift(&packages, &[], &repositories);
+    assert_eq!(
+        drift,
+        Drift {
+            missing: vec!["git".to_string()],
+            extra: vec!["gcc".to_string()],
+            unheld: vec!["linux".to_string()],
+            disabled_repositories: vec!["multilib".to_string()],
+            unknown_repositories: vec!["chaotic-aur".to_string()],
+        }
+    );
+    assert_eq!(drift.count(), 5);
+
+    let transaction = drift.transaction(&profile);
+    assert_eq!(transaction.install, vec!["git"]);
+    assert!(transaction.remove.is_empty());
+    assert_eq!(transaction.ignore, vec!["linux"]);
+    assert_eq!(transaction.repositories, vec!["multilib"]);
+    assert!(drift.transaction(&Profile { remove_extra: true, ..profile.clone() }).remove.contains(&"gcc".to_string()));
+
+    let current = Profile::from_system(&packages, &["linux".to_string()], &repositories);
+    assert_eq!(current.packages, vec!["gcc", "vim"]);
+    assert_eq!(current.repositories, vec!["core"]);
+    assert_eq!(current.drift(&packages, &["linux".to_string()], &repositories).count(), 0);
+
+    let path = std::env::temp_dir().join(format!("pacmanager-profile-{}", std::process::id())).join("profile.toml");
+    save(&path, &current).unwrap();
+    assert_eq!(load(&path).unwrap(), current);
+
+    let written = "# Workstation\npackages = [\"vim\", \"git\"] # editors\n\nholds = []\n";
+    std::fs::write(&path, written).unwrap();
+    set_remove_extra(&path, true).unwrap();
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(content.starts_with(written));
+    assert!(load(&path).unwrap().remove_extra);
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
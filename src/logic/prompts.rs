@@ -0,0 +1,317 @@
+use std::collections::BTreeMap;
+
+use crate::logic::keyring::{self, Key};
+use crate::logic::package::Package;
+use crate::logic::transaction::{ASK_CONFLICT, ASK_IMPORT_KEY, Transaction};
+
+//A choice pacman would otherwise make on its own under --noconfirm
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Question {
+    //Nothing is named `dependency`, several packages provide it. `default` is the one pacman picked in the preview
+    Provider {
+        dependency: String,
+        required_by: String,
+        providers: Vec<String>,
+        default: String,
+    },
+    //`package` can only be installed once the installed `installed` is removed
+    Conflict { package: String, installed: String },
+    //None of the keys in the keyring belongs to the packager, pacman asks to import it while downloading
+    ImportKey { package: String, packager: String },
+}
+
+//What the user answered, turned into explicit targets and --ask flags by `apply`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Answers {
+    //Dependency to the chosen provider
+    pub providers: BTreeMap<String, String>,
+    pub replace_conflicts: bool,
+    pub skip_key_import: bool,
+}
+
+//"jack>=1.9" -> "jack", used for the Depends On, Provides and Conflicts With entries
+pub fn strip_constraint(entry: &str) -> &str {
+    entry.split(['<', '>', '=']).next().unwrap_or_default()
+}
+
+fn name_of(package: &Package) -> String {
+    package.get_property("Name".to_string()).unwrap_or_default()
+}
+
+fn satisfies(package: &Package, dependency: &str) -> bool {
+    name_of(package) == dependency
+        || package
+            .get_list_property("Provides".to_string())
+            .iter()
+            .any(|p| strip_constraint(p) == dependency)
+}
+
+//Packages able to satisfy `dependency`, by name
+pub fn providers(packages: &[Package], dependency: &str) -> Vec<String> {
+    let mut names = packages
+        .iter()
+        .filter(|p| satisfies(p, dependency))
+        .map(name_of)
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+    names
+}
+
+//Finds the questions a transaction raises. `packages` holds one entry per package name with its sync
+//and installed fields, `resolved` the "name version" lines of the preview and `keys` the keyring when it could be read
+pub fn questions(packages: &[Package], transaction: &Transaction, resolved: &[String], keys: Option<&[Key]>) -> Vec<Question> {
+    let resolved = resolved
+        .iter()
+        .filter_map(|x| x.split_whitespace().next())
+        .filter_map(|name| packages.iter().find(|p| name_of(p) == name))
+        .collect::<Vec<_>>();
+    let mut questions = vec![];
+
+    //pacman only asks when no package carries the exact name, the resolved set tells which provider it picked
+    let mut provider_question = |dependency: &str, required_by: String| {
+        if packages.iter().any(|p| name_of(p) == dependency)
+            || packages.iter().any(|p| p.is_installed() && satisfies(p, dependency))
+            || questions.iter().any(|q| matches!(q, Question::Provider { dependency: d, .. } if d == dependency))
+        {
+            return;
+        }
+        let providers = providers(packages, dependency);
+        if providers.len() < 2 {
+            return;
+        }
+        let default = resolved
+            .iter()
+            .find(|p| satisfies(p, dependency))
+            .map(|p| name_of(p))
+            .unwrap_or(providers[0].clone());
+        questions.push(Question::Provider {
+            dependency: dependency.to_string(),
+            required_by,
+            providers,
+            default,
+        });
+    };
+    for target in transaction.install.iter() {
+        let name = target.rsplit('/').next().unwrap_or_default();
+        provider_question(name, String::new());
+    }
+    for package in resolved.iter() {
+        for dependency in package.get_list_property("Depends On".to_string()) {
+            provider_question(strip_constraint(&dependency), name_of(package));
+        }
+    }
+
+    for package in resolved.iter() {
+        for conflict in package.get_list_property("Conflicts With".to_string()) {
+            let conflict = strip_constraint(&conflict);
+            for installed in packages.iter().filter(|p| p.is_installed() && satisfies(p, conflict)) {
+                let (package, installed) = (name_of(package), name_of(installed));
+                let question = Question::Conflict { package: package.clone(), installed: installed.clone() };
+                if package != installed && !transaction.remove.contains(&installed) && !questions.contains(&question) {
+                    questions.push(question);
+                }
+            }
+        }
+    }
+
+    if let Some(keys) = keys {
+        let mut asked: Vec<String> = vec![];
+        for package in resolved.iter().filter(|p| !p.is_installed()) {
+            let Some(packager) = package.get_property("Packager".to_string()) else {
+                continue;
+            };
+            if keyring::packager_keys(keys, &packager).is_empty() && !asked.contains(&packager) {
+                asked.push(packager.clone());
+                questions.push(Question::ImportKey { package: name_of(package), packager });
+            }
+        }
+    }
+
+    questions
+}
+
+impl Answers {
+    //Starts from what pacman would do on its own, except for conflicts which it refuses by default
+    pub fn defaults(questions: &[Question]) -> Answers {
+        let mut answers = Answers::default();
+        for question in questions {
+            if let Question::Provider { dependency, default, .. } = question {
+                answers.providers.insert(dependency.clone(), default.clone());
+            }
+        }
+        answers
+    }
+
+    //Takes the questions found once the preview ran again with these answers applied. Provider questions asked
+    //before are kept so a choice can be changed back, new ones start from their default. Conflicts have to be
+    //agreed to again whenever the set of conflicts changes
+    pub fn revise(&mut self, asked: &[Question], found: Vec<Question>) -> Vec<Question> {
+        let conflicts = |questions: &[Question]| {
+            questions.iter().filter(|q| matches!(q, Question::Conflict { .. })).cloned().collect::<Vec<_>>()
+        };
+        let mut questions = asked.iter().filter(|q| matches!(q, Question::Provider { .. })).cloned().collect::<Vec<_>>();
+        for question in found {
+            let asked = matches!(&question, Question::Provider { dependency, .. }
+                if questions.iter().any(|q| matches!(q, Question::Provider { dependency: d, .. } if d == dependency)));
+            if !asked {
+                questions.push(question);
+            }
+        }
+        for (dependency, default) in Answers::defaults(&questions).providers {
+            self.providers.entry(dependency).or_insert(default);
+        }
+        if conflicts(asked) != conflicts(&questions) {
+            self.replace_conflicts = false;
+        }
+        questions
+    }
+
+    //Chosen providers become explicit dependencies, the rest is answered through pacman's --ask
+    pub fn apply(&self, questions: &[Question], transaction: &mut Transaction) {
+        for question in questions {
+            match question {
+                Question::Provider { dependency, required_by, .. } => {
+                    let Some(provider) = self.providers.get(dependency) else {
+                        continue;
+                    };
+                    //A virtual target is replaced by the provider, which then is what the user asked for
+                    if required_by.is_empty() {
+                        for target in transaction.install.iter_mut() {
+                            if target.rsplit('/').next() == Some(dependency.as_str()) {
+                                *target = provider.clone();
+                            }
+                        }
+                    } else if !transaction.providers.contains(provider) {
+                        transaction.providers.push(provider.clone());
+                    }
+                }
+                Question::Conflict { .. } if self.replace_conflicts => transaction.ask |= ASK_CONFLICT,
+                Question::ImportKey { .. } if self.skip_key_import => transaction.ask |= ASK_IMPORT_KEY,
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Question {
+    pub fn describe(&self) -> String {
+        match self {
+            Question::Provider { dependency, required_by, .. } if required_by.is_empty() => {
+                format!("Several packages provide {}", dependency)
+            }
+            Question::Provider { dependency, required_by, .. } => {
+                format!("{} needs {}, which several packages provide", required_by, dependency)
+            }
+            Question::Conflict { package, installed } => format!("{} conflicts with the installed {}", package, installed),
+            Question::ImportKey { package, packager } => format!("{} is signed by {}, whose key is not in the keyring", package, packager),
+        }
+    }
+}
+
+#[test]
+fn test_prompts() {
+    let raw = |lines: &[&str]| Package::from_raw(lines.iter().map(|x| x.to_string()).collect());
+    let packages = vec![
+        raw(&[
+            "Name            : ardour",
+            "Depends On      : jack>=1.9 glibc",
+            "Packager        : David Runge <dvzrv@archlinux.org>",
+        ]),
+        raw(&["Name            : jack2", "Provides        : jack=1.9.22", "Packager        : David Runge <dvzrv@archlinux.org>"]),
+        raw(&[
+            "Name            : pipewire-jack",
+            "Provides        : jack libjack.so=0-64",
+            "Conflicts With  : jack jack2",
+            "Packager        : Jan Alexander Steffens (heftig) <heftig@archlinux.org>",
+        ]),
+        raw(&["Name            : glibc", "Installed       : True"]),
+    ];
+    let mut transaction = Transaction {
+        install: vec!["ardour".to_string()],
+        ..Transaction::default()
+    };
+    let resolved = vec!["ardour 8.0-1".to_string(), "jack2 1.9.22-1".to_string()];
+    let keys = vec![Key {
+        uids: vec![("full".to_string(), "Jan Alexander Steffens (heftig) <heftig@archlinux.org>".to_string())],
+        ..Key::default()
+    }];
+
+    assert_eq!(strip_constraint("jack>=1.9"), "jack");
+    assert_eq!(providers(&packages, "jack"), vec!["jack2", "pipewire-jack"]);
+
+    let found = questions(&packages, &transaction, &resolved, Some(&keys));
+    assert_eq!(
+        found,
+        vec![
+            Question::Provider {
+                dependency: "jack".to_string(),
+                required_by: "ardour".to_string(),
+                providers: vec!["jack2".to_string(), "pipewire-jack".to_string()],
+                default: "jack2".to_string(),
+            },
+            Question::ImportKey {
+                package: "ardour".to_string(),
+                packager: "David Runge <dvzrv@archlinux.org>".to_string(),
+            },
+        ]
+    );
+
+    //Picking pipewire-jack while jack2 is installed raises a conflict
+    let mut installed = packages.clone();
+    installed[1].set_property("Installed".to_string(), "True".to_string());
+    let resolved = vec!["ardour 8.0-1".to_string(), "pipewire-jack 1.0-1".to_string()];
+    let found = questions(&installed, &transaction, &resolved, None);
+    assert_eq!(
+        found,
+        vec![Question::Conflict {
+            package: "pipewire-jack".to_string(),
+            installed: "jack2".to_string(),
+        }]
+    );
+
+    let mut answers = Answers::defaults(&found);
+    answers.replace_conflicts = true;
+    answers.apply(&found, &mut transaction);
+    assert_eq!(transaction.ask, ASK_CONFLICT);
+
+    //Choosing pipewire-jack over the default runs the preview again, which now finds the conflict
+    let asked = vec![Question::Provider {
+        dependency: "jack".to_string(),
+        required_by: "ardour".to_string(),
+        providers: vec!["jack2".to_string(), "pipewire-jack".to_string()],
+        default: "jack2".to_string(),
+    }];
+    let mut answers = Answers::defaults(&asked);
+    answers.providers.insert("jack".to_string(), "pipewire-jack".to_string());
+    let mut again = Transaction {
+        install: vec!["ardour".to_string()],
+        ..Transaction::default()
+    };
+    answers.apply(&asked, &mut again);
+    assert_eq!(again.providers, vec!["pipewire-jack"]);
+    let revised = answers.revise(&asked, questions(&installed, &again, &resolved, None));
+    assert_eq!(revised.len(), 2);
+    assert_eq!(revised[0], asked[0]);
+    assert!(matches!(&revised[1], Question::Conflict { installed, .. } if installed == "jack2"));
+    assert_eq!(answers.providers["jack"], "pipewire-jack");
+    answers.replace_conflicts = true;
+    assert_eq!(answers.revise(&revised, vec![]), asked);
+    assert!(!answers.replace_conflicts);
+
+    let questions = vec![Question::Provider {
+        dependency: "jack".to_string(),
+        required_by: String::new(),
+        providers: vec!["jack2".to_string(), "pipewire-jack".to_string()],
+        default: "jack2".to_string(),
+    }];
+    let mut transaction = Transaction {
+        install: vec!["jack".to_string()],
+        ..Transaction::default()
+    };
+    let mut answers = Answers::defaults(&questions);
+    answers.providers.insert("jack".to_string(), "pipewire-jack".to_string());
+    answers.apply(&questions, &mut transaction);
+    assert_eq!(transaction.install, vec!["pipewire-jack"]);
+    assert!(transaction.providers.is_empty());
+}
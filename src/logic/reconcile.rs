@@ -0,0 +1,189 @@
+use serde_json::Value;
+use std::path::Path;
+
+use crate::logic::cache::CacheGroup;
+use crate::logic::package::Package;
+use crate::logic::transaction::Transaction;
+
+//An entry of an imported list, the version is only known for lists carrying one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WantedPackage {
+    pub name: String,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionDifference {
+    pub name: String,
+    pub wanted: String,
+    pub installed: String,
+}
+
+//How the system differs from an imported list
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Reconciliation {
+    pub missing: Vec<WantedPackage>,
+    //Explicitly installed packages the list does not mention, dependencies come and go with what needs them
+    pub extra: Vec<String>,
+    pub differs: Vec<VersionDifference>,
+}
+
+//Reads the JSON written by the export, or a plain list with one name per line.
+//Plain lines may carry a version like `pacman -Q` prints them, "#" starts a comment
+pub fn parse_list(content: &str) -> Result<Vec<WantedPackage>, String> {
+    if content.trim_start().starts_with('[') {
+        let rows: Vec<Value> = serde_json::from_str(content).map_err(|e| format!("Invalid package list: {}", e))?;
+        return rows
+            .iter()
+            .map(|row| {
+                let name = row["name"].as_str().filter(|n| !n.is_empty()).ok_or(format!("Entry without a name: {}", row))?;
+                Ok(WantedPackage {
+                    name: name.to_string(),
+                    version: row["version"].as_str().filter(|v| !v.is_empty()).map(|v| v.to_string()),
+                })
+            })
+            .collect();
+    }
+
+    Ok(content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            Some(WantedPackage {
+                name: words.next()?.to_string(),
+                version: words.next().map(|v| v.to_string()),
+            })
+        })
+        .collect())
+}
+
+pub fn load(path: &Path) -> Result<Vec<WantedPackage>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    parse_list(&content)
+}
+
+//Compares the list with the installed entries of `packages`
+pub fn reconcile(wanted: &[WantedPackage], packages: &[Package]) -> Reconciliation {
+    let property = |p: &Package, name: &str| p.get_property(name.to_string()).unwrap_or_default();
+    let installed = packages.iter().filter(|p| p.is_installed()).collect::<Vec<_>>();
+    let mut result = Reconciliation::default();
+
+    for entry in wanted {
+        match installed.iter().find(|p| property(p, "Name") == entry.name) {
+            None => result.missing.push(entry.clone()),
+            Some(package) => {
+                let version = property(package, "Version");
+                if let Some(wanted) = &entry.version
+                    && *wanted != version
+                {
+                    result.differs.push(VersionDifference {
+                        name: entry.name.clone(),
+                        wanted: wanted.clone(),
+                        installed: version,
+                    });
+                }
+            }
+        }
+    }
+
+    result.extra = installed
+        .iter()
+        .filter(|p| property(p, "Install Reason").starts_with("Explicitly"))
+        .map(|p| property(p, "Name"))
+        .filter(|name| !wanted.iter().any(|w| w.name == *name))
+        .collect();
+    result.extra.sort();
+    result
+}
+
+impl Reconciliation {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.differs.is_empty()
+    }
+
+    //Missing packages come from the repositories at their current version
+    pub fn install_missing(&self) -> Transaction {
+        Transaction {
+            description: format!("Install {} missing package(s)", self.missing.len()),
+            install: self.missing.iter().map(|w| w.name.clone()).collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn remove_extra(&self) -> Transaction {
+        Transaction {
+            description: format!("Remove {} package(s) missing from the list", self.extra.len()),
+            remove: self.extra.clone(),
+            ..Default::default()
+        }
+    }
+
+    //Wanted versions are installed from the cache, the repositories only have the current one
+    pub fn align_versions(&self, cache: &[CacheGroup]) -> Transaction {
+        let mut transaction = Transaction {
+            description: format!("Align the version of {} package(s)", self.differs.len()),
+            ..Default::default()
+        };
+        for difference in self.differs.iter() {
+            let cached = cache
+                .iter()
+                .find(|g| g.name == difference.name)
+                .and_then(|g| g.versions.iter().find(|v| v.version == difference.wanted));
+            match cached {
+                Some(cached) => transaction.install_files.push(cached.path.to_string_lossy().to_string()),
+                None => transaction.notes.push(format!(
+                    "{} {} is not cached, it will stay at {}",
+                    difference.name, difference.wanted, difference.installed
+                )),
+            }
+        }
+        transaction
+    }
+}
+
+#[test]
+fn test_reconcile() {
+    let raw = |lines: &[&str]| Package::from_raw(lines.iter().map(|x| x.to_string()).collect());
+    let packages = vec![
+        raw(&["Name            : vim", "Version         : 9.1.0-1", "Installed       : True", "Install Reason  : Explicitly installed"]),
+        raw(&["Name            : gcc", "Version         : 14.1-1", "Installed       : True", "Install Reason  : Explicitly installed"]),
+        raw(&[
+            "Name            : glibc",
+            "Version         : 2.39-1",
+            "Installed       : True",
+            "Install Reason  : Installed as a dependency for another package",
+        ]),
+        raw(&["Name            : git", "Version         : 2.45-1", "Installed       : False"]),
+    ];
+
+    let plain = parse_list("# dev machine\nvim 9.0.0-1\ngit\n\n  \n").unwrap();
+    assert_eq!(
+        plain,
+        vec![
+            WantedPackage { name: "vim".to_string(), version: Some("9.0.0-1".to_string()) },
+            WantedPackage { name: "git".to_string(), version: None },
+        ]
+    );
+    let json = parse_list(r#"[{"name": "vim", "version": "9.0.0-1", "repository": "extra"}, {"name": "git"}]"#).unwrap();
+    assert_eq!(json, plain);
+    assert!(parse_list(r#"[{"version": "1"}]"#).is_err());
+
+    let result = reconcile(&plain, &packages);
+    assert_eq!(result.missing, vec![plain[1].clone()]);
+    assert_eq!(result.extra, vec!["gcc"]);
+    assert_eq!(
+        result.differs,
+        vec![VersionDifference {
+            name: "vim".to_string(),
+            wanted: "9.0.0-1".to_string(),
+            installed: "9.1.0-1".to_string(),
+        }]
+    );
+    assert_eq!(result.install_missing().install, vec!["git"]);
+    assert_eq!(result.remove_extra().remove, vec!["gcc"]);
+
+    let aligned = result.align_versions(&[]);
+    assert!(aligned.is_empty());
+    assert_eq!(aligned.notes.len(), 1);
+}
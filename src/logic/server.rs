@@ -1,15 +1,28 @@
 use rust_fuzzy_search::fuzzy_compare;
 
+use crate::logic::aur::Aur;
+use crate::logic::files;
+use crate::logic::hold;
 use crate::logic::package::Package;
+use crate::logic::pacman_conf::{self, PACMAN_CONF};
+use crate::logic::privileged;
+use crate::logic::version::vercmp;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
     process::Command,
     sync::{Arc, Mutex},
 };
 
 #[derive(Debug, Clone, Default)]
 pub struct Server {
-    packages: HashMap<String, Arc<Mutex<Package>>>,
+    //Every entry of a package name, one per repository, in pacman.conf priority order
+    packages: HashMap<String, Vec<Arc<Mutex<Package>>>>,
+    //Repository names in the order pacman reports them
+    repositories: Vec<String>,
+    //Group name to member package names, sorted
+    groups: BTreeMap<String, Vec<String>>,
+    pub aur: Aur,
 }
 
 impl Server {
@@ -22,30 +35,82 @@ impl Server {
         let installed =
             String::from_utf8(Command::new("pacman").arg("-Q").output().unwrap().stdout).unwrap();
 
+        //Everything is reset first so packages removed since the last check are picked up
+        for package in self.packages.values().flatten() {
+            let mut package = package.lock().unwrap();
+            package.set_property("Installed".to_string(), "False".to_string());
+            package.remove_property("Upgradable".to_string());
+            package.remove_property("New Version".to_string());
+            package.remove_property("Orphan".to_string());
+            package.remove_property("Foreign".to_string());
+            package.remove_property("Held".to_string());
+        }
+
         for line in installed.split("\n") {
             let name = line.split(" ").collect::<Vec<&str>>()[0].to_string();
             if name.len() == 0 {
                 continue;
             }
-            self.get_package(name)
-                .unwrap()
-                .lock()
-                .unwrap()
-                .set_property("Installed".to_string(), "True".to_string());
-        }
-
-        for p in self.packages.keys() {
-            let package = self.get_package(p.clone()).unwrap().clone();
-            if package
-                .lock()
-                .unwrap()
-                .get_property("Installed".to_string())
-                .is_none()
-            {
+            for package in self.get_variants(name) {
                 package
                     .lock()
                     .unwrap()
-                    .set_property("Installed".to_string(), "False".to_string());
+                    .set_property("Installed".to_string(), "True".to_string());
+            }
+        }
+
+
+        //Held packages are never offered as upgrades, whether they are held in IgnorePkg or by the app
+        let ignored = pacman_conf::options(Path::new(PACMAN_CONF), "IgnorePkg");
+        let app_holds = hold::load(&hold::hold_file());
+        for package in self.packages.values().flatten() {
+            let mut package = package.lock().unwrap();
+            let name = package.get_property("Name".to_string()).unwrap_or_default();
+            if app_holds.contains(&name) || ignored.iter().any(|p| hold::pattern_matches(p, &name)) {
+                package.set_property("Held".to_string(), "True".to_string());
+            }
+        }
+
+        let upgradable =
+            String::from_utf8(Command::new("pacman").arg("-Qu").output().unwrap().stdout).unwrap();
+
+        //Lines look like "name 1.0-1 -> 1.1-1", ignored packages have an extra "[ignored]" suffix
+        for line in upgradable.split("\n") {
+            let parts = line.split_whitespace().collect::<Vec<&str>>();
+            if parts.len() < 4 || parts.contains(&"[ignored]") {
+                continue;
+            }
+            for p in self.get_variants(parts[0].to_string()) {
+                let mut p = p.lock().unwrap();
+                if p.is_held() {
+                    continue;
+                }
+                p.set_property("Upgradable".to_string(), "True".to_string());
+                p.set_property("New Version".to_string(), parts[3].to_string());
+            }
+        }
+
+        let orphans =
+            String::from_utf8(Command::new("pacman").arg("-Qdtq").output().unwrap().stdout).unwrap();
+
+        for name in orphans.split("\n").filter(|x| !x.is_empty()) {
+            for p in self.get_variants(name.to_string()) {
+                p.lock()
+                    .unwrap()
+                    .set_property("Orphan".to_string(), "True".to_string());
+            }
+        }
+
+        //Same semantics as `pacman -Qm`: installed packages missing from every sync database,
+        //which covers AUR builds and local archives
+        let foreign =
+            String::from_utf8(Command::new("pacman").arg("-Qmq").output().unwrap().stdout).unwrap();
+
+        for name in foreign.split("\n").filter(|x| !x.is_empty()) {
+            for p in self.get_variants(name.to_string()) {
+                p.lock()
+                    .unwrap()
+                    .set_property("Foreign".to_string(), "True".to_string());
             }
         }
 
@@ -53,18 +118,76 @@ impl Server {
     }
 
     pub fn populate(&mut self) -> Server {
-        let mut pacman =
+        let sync =
             String::from_utf8(Command::new("pacman").arg("-Si").output().unwrap().stdout).unwrap();
-        pacman += "\n";
-        pacman +=
-            &String::from_utf8(Command::new("pacman").arg("-Qi").output().unwrap().stdout).unwrap();
+        let local =
+            String::from_utf8(Command::new("pacman").arg("-Qi").output().unwrap().stdout).unwrap();
+
+        let mut packages: HashMap<String, Vec<Arc<Mutex<Package>>>> = HashMap::new();
+        let mut repositories: Vec<String> = vec![];
+
+        for new_package in Self::parse_packages(sync) {
+            let repo = new_package
+                .get_property("Repository".to_string())
+                .unwrap_or_default();
+            if !repositories.contains(&repo) {
+                repositories.push(repo);
+            }
+            packages
+                .entry(
+                    new_package
+                        .get_property("Name".to_string())
+                        .unwrap_or_default(),
+                )
+                .or_default()
+                .push(Arc::new(Mutex::new(new_package)));
+        }
+
+        //Local entries are merged on top of the sync ones so that fields only present in
+        //the sync database (such as "Repository") survive for installed packages.
+        //When a name lives in several repositories the entry with the installed version wins
+        for new_package in Self::parse_packages(local) {
+            let name = new_package
+                .get_property("Name".to_string())
+                .unwrap_or_default();
+            let version = new_package.get_property("Version".to_string());
+            match packages.get(&name) {
+                Some(variants) => {
+                    let target = variants
+                        .iter()
+                        .find(|p| p.lock().unwrap().get_property("Version".to_string()) == version)
+                        .unwrap_or(&variants[0]);
+                    target.lock().unwrap().merge(new_package)
+                }
+                None => {
+                    packages.insert(name, vec![Arc::new(Mutex::new(new_package))]);
+                }
+            }
+        }
+
+        let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (name, variants) in packages.iter() {
+            for group in variants[0].lock().unwrap().get_list_property("Groups".to_string()) {
+                groups.entry(group).or_default().push(name.clone());
+            }
+        }
+        groups.values_mut().for_each(|members| members.sort());
 
+        self.packages = packages.clone();
+        self.repositories = repositories;
+        self.groups = groups;
+
+        return self.clone();
+    }
+
+    //Splits the output of `pacman -Si` or `pacman -Qi` into individual packages
+    fn parse_packages(raw: String) -> Vec<Package> {
         let mut packages_raw: Vec<Vec<String>> = vec![];
         let mut curr_package: Vec<String> = vec![];
 
-        for line in pacman.split("\n") {
-            if line.len() == 0 {
-                if curr_package.len() > 0 {
+        for line in raw.split("\n").chain(std::iter::once("")) {
+            if line.is_empty() {
+                if !curr_package.is_empty() {
                     packages_raw.push(curr_package.clone())
                 };
                 curr_package.clear();
@@ -73,40 +196,182 @@ impl Server {
             curr_package.push(line.to_string());
         }
 
-        let mut packages = HashMap::new();
+        packages_raw.into_iter().map(Package::from_raw).collect()
+    }
 
-        for p in packages_raw {
-            let new_package = Package::from_raw(p);
-            packages.insert(
-                new_package
-                    .get_property("Name".to_string())
-                    .unwrap_or_default(),
-                Arc::new(Mutex::new(new_package)),
-            );
+    //Accepts either a bare name, resolved to the highest priority repository, or "repo/name"
+    pub fn get_package(&self, name: String) -> Option<Arc<Mutex<Package>>> {
+        let name = name.trim();
+        if let Some((repo, name)) = name.split_once("/") {
+            return self.get_package_in(repo.to_string(), name.to_string());
         }
+        self.packages.get(name).and_then(|v| v.first().cloned())
+    }
 
-        self.packages = packages.clone();
+    pub fn get_package_in(&self, repo: String, name: String) -> Option<Arc<Mutex<Package>>> {
+        self.get_variants(name).into_iter().find(|p| {
+            p.lock()
+                .unwrap()
+                .get_property("Repository".to_string())
+                .unwrap_or_default()
+                == repo
+        })
+    }
 
-        return self.clone();
+    //All the repository entries sharing a package name (e.g. core-testing and core)
+    pub fn get_variants(&self, name: String) -> Vec<Arc<Mutex<Package>>> {
+        self.packages.get(name.trim()).cloned().unwrap_or_default()
     }
 
-    pub fn get_package(&self, name: String) -> Option<Arc<Mutex<Package>>> {
-        match self.packages.get(name.clone().trim()) {
-            Some(p) => Some(p.clone()),
-            None => None,
+    //Package count of every repository, in priority order
+    pub fn get_repository_counts(&self) -> Vec<(String, usize)> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for p in self.packages.values().flatten() {
+            if let Some(repo) = p.lock().unwrap().get_property("Repository".to_string()) {
+                *counts.entry(repo).or_default() += 1;
+            }
         }
+        self.repositories
+            .iter()
+            .map(|r| (r.clone(), counts.get(r).cloned().unwrap_or_default()))
+            .collect()
     }
 
-    pub fn search(&self, query: String) -> Vec<Arc<Mutex<Package>>> {
-        println!(
-            "Querying database against: \"{}\"\n Server has {} packages",
-            query,
-            self.packages.keys().len()
-        );
+    //Every group with its member packages, sorted by group name
+    pub fn get_groups(&self) -> Vec<(String, Vec<Arc<Mutex<Package>>>)> {
+        self.groups
+            .iter()
+            .map(|(group, members)| {
+                (
+                    group.clone(),
+                    members
+                        .iter()
+                        .filter_map(|m| self.get_package(m.clone()))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
 
+    //Installed packages as `pacman -Qi` reports them at the time of the call, the one read for anything that
+    //compares versions, reasons or orphans
+    pub fn installed(&self) -> Vec<Package> {
+        let output = Command::new("pacman").arg("-Qi").output().map(|o| o.stdout).unwrap_or_default();
+        self.installed_from(String::from_utf8_lossy(&output).to_string())
+    }
+
+    //Every entry of the `pacman -Qi` output on top of a copy of its sync entry, so fields such as "Repository" survive.
+    //"Orphan" follows `pacman -Qdt`: a dependency nothing requires or optionally requires
+    pub fn installed_from(&self, local: String) -> Vec<Package> {
+        Self::parse_packages(local)
+            .into_iter()
+            .map(|local| {
+                let name = local.get_property("Name".to_string()).unwrap_or_default();
+                let version = local.get_property("Version".to_string());
+                let variants = self.get_variants(name);
+                let mut package = variants
+                    .iter()
+                    .find(|p| p.lock().unwrap().get_property("Version".to_string()) == version)
+                    .or(variants.first())
+                    .map(|p| p.lock().unwrap().clone())
+                    .unwrap_or_default();
+                package.merge(local);
+                package.set_property("Installed".to_string(), "True".to_string());
+                package.remove_property("Orphan".to_string());
+                let unrequired = |field: &str| package.get_property(field.to_string()).is_none_or(|v| v.trim() == "None");
+                if package.get_property("Install Reason".to_string()).unwrap_or_default().starts_with("Installed as a dependency")
+                    && unrequired("Required By")
+                    && unrequired("Optional For")
+                {
+                    package.set_property("Orphan".to_string(), "True".to_string());
+                }
+                package
+            })
+            .collect()
+    }
+
+    //`installed` followed by the first sync entry of every package it does not contain, for checks that
+    //also look at what could be installed, such as finding providers
+    pub fn with_sync_entries(&self, installed: Vec<Package>) -> Vec<Package> {
+        let names = installed
+            .iter()
+            .map(|p| p.get_property("Name".to_string()).unwrap_or_default())
+            .collect::<HashSet<_>>();
+        let mut packages = installed;
+        for (name, variants) in self.packages.iter() {
+            if names.contains(name) {
+                continue;
+            }
+            if let Some(first) = variants.first() {
+                let mut package = first.lock().unwrap().clone();
+                package.set_property("Installed".to_string(), "False".to_string());
+                packages.push(package);
+            }
+        }
+        packages
+    }
+
+    pub fn get_repository_packages(&self, repo: String) -> Vec<Arc<Mutex<Package>>> {
+        let mut result = self
+            .packages
+            .values()
+            .flatten()
+            .filter(|p| {
+                p.lock()
+                    .unwrap()
+                    .get_property("Repository".to_string())
+                    .unwrap_or_default()
+                    == repo
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        result.sort_by_key(|p| p.lock().unwrap().get_property("Name".to_string()));
+        result
+    }
+
+    //Installed package owning a file, `target` can be a path or a command name (`pacman -Qo` semantics)
+    pub fn owner_of(&self, target: String) -> Option<Arc<Mutex<Package>>> {
+        let path = files::resolve_target(&target)?;
+        let name = files::owner_in_db(std::path::Path::new(files::DB_PATH), &path)?;
+        self.get_package(name)
+    }
+
+    //Packages whose files database entry provides `target` (`pacman -F`), installed or not
+    pub fn search_files(&self, target: String) -> Vec<Arc<Mutex<Package>>> {
+        let output = String::from_utf8(
+            Command::new("pacman")
+                .arg("-F")
+                .arg(target)
+                .output()
+                .unwrap()
+                .stdout,
+        )
+        .unwrap();
+
+        //Result lines look like "extra/vim 9.1-1 (group)", matching files are indented below
+        output
+            .split("\n")
+            .filter(|x| !x.starts_with(char::is_whitespace))
+            .filter_map(|x| x.split(" ").next()?.split_once("/"))
+            .filter_map(|(repo, name)| self.get_package_in(repo.to_string(), name.to_string()))
+            .collect()
+    }
+
+    pub fn search(&self, query: String) -> Vec<Arc<Mutex<Package>>> {
         let pacman_search = String::from_utf8(
             std::process::Command::new("pacman")
                 .arg("-Ss")
+                .arg(query.clone())
+                .output()
+                .unwrap()
+                .stdout,
+        )
+        .unwrap();
+
+        //Foreign packages are only known to the local database
+        let local_search = String::from_utf8(
+            std::process::Command::new("pacman")
+                .arg("-Qs")
                 .arg(query)
                 .output()
                 .unwrap()
@@ -115,25 +380,91 @@ impl Server {
         .unwrap();
 
         //Its best to move this outside the function to avoid deadlocks
-        let result = pacman_search
+        //Result lines look like "extra/vim 9.1-1 [installed]", descriptions are indented
+        let mut result = pacman_search
             .split("\n")
-            .clone()
-            .into_iter()
-            .filter(|x| x.contains("/"))
-            .map(|x| x.split("/").collect::<Vec<_>>()[1].split(" ").collect::<Vec<_>>()[0])
-            .filter(|x| self.get_package(x.to_string()).is_some())
-            .map(|x| self.get_package(x.to_string()).unwrap())
+            .filter(|x| !x.starts_with(char::is_whitespace))
+            .filter_map(|x| x.split(" ").next()?.split_once("/"))
+            .filter_map(|(repo, name)| self.get_package_in(repo.to_string(), name.to_string()))
             .collect::<Vec<Arc<Mutex<Package>>>>();
 
+        result.extend(
+            local_search
+                .split("\n")
+                .filter(|x| x.starts_with("local/"))
+                .filter_map(|x| self.get_package(x["local/".len()..].split(" ").next()?.to_string()))
+                .filter(|p| p.lock().unwrap().is_foreign()),
+        );
+
         return result;
     }
 
+    //AUR results for `query`. Packages that are already installed resolve to their local entry
+    pub fn search_aur(&self, query: String) -> Result<Vec<Arc<Mutex<Package>>>, String> {
+        let results = self.aur.0.search(query)?;
+
+        Ok(results
+            .into_iter()
+            .map(|r| match self.get_package(r.name.clone()) {
+                Some(local) if local.lock().unwrap().is_foreign() => {
+                    Self::mark_aur(&local, &r.package_base, &r.version);
+                    local
+                }
+                _ => Arc::new(Mutex::new(r.to_package())),
+            })
+            .collect())
+    }
+
+    fn mark_aur(package: &Arc<Mutex<Package>>, package_base: &str, version: &str) {
+        let mut package = package.lock().unwrap();
+        package.set_property("AUR".to_string(), "True".to_string());
+        package.set_property("Package Base".to_string(), package_base.to_string());
+        let local = package.get_property("Version".to_string()).unwrap_or_default();
+        if vercmp(version, &local).is_gt() && !package.is_held() {
+            package.set_property("Upgradable".to_string(), "True".to_string());
+            package.set_property("New Version".to_string(), version.to_string());
+        }
+    }
+
+    //Looks up every foreign package in the AUR and flags the ones with a newer version.
+    //Returns how many updates are available
+    pub fn check_aur_updates(&self) -> Result<usize, String> {
+        let foreign = self
+            .packages
+            .values()
+            .flatten()
+            .filter(|p| p.lock().unwrap().is_foreign())
+            .cloned()
+            .collect::<Vec<_>>();
+        let names = foreign
+            .iter()
+            .map(|p| p.lock().unwrap().get_property("Name".to_string()).unwrap_or_default())
+            .collect::<Vec<_>>();
+
+        let results = self.aur.0.info(names)?;
+        for r in results.iter() {
+            if let Some(local) = self.get_package(r.name.clone()) {
+                Self::mark_aur(&local, &r.package_base, &r.version);
+            }
+        }
+
+        Ok(foreign
+            .iter()
+            .filter(|p| {
+                let p = p.lock().unwrap();
+                p.get_property("AUR".to_string()).is_some()
+                    && p.get_property("Upgradable".to_string()).is_some()
+            })
+            .count())
+    }
+
     pub fn system_update(&mut self) -> String {
-        let command = std::process::Command::new("sh")
-            .arg("-c")
-            .arg("pkexec pacman -Syu --noconfirm")
-            .output();
-        return String::from_utf8(command.unwrap().stderr).unwrap()
+        self.system_update_status().1
+    }
+
+    //Like `system_update`, also telling whether pacman succeeded since its warnings go to stderr too
+    pub fn system_update_status(&mut self) -> (bool, String) {
+        privileged::run_status(format!("pacman -Syu --noconfirm{}", hold::ignore_args(&hold::load(&hold::hold_file()))))
     }
 }
 
@@ -156,3 +487,60 @@ fn test_server() {
             == "False".to_string()
     );
 }
+
+#[test]
+fn test_installed_from() {
+    let raw = |lines: &[&str]| Arc::new(Mutex::new(Package::from_raw(lines.iter().map(|x| x.to_string()).collect())));
+    let mut server = Server::default();
+    //vim was upgraded after the local database was merged at startup
+    server.packages.insert(
+        "vim".to_string(),
+        vec![raw(&[
+            "Repository      : extra",
+            "Name            : vim",
+            "Version         : 9.0-1",
+            "Installed       : True",
+            "Install Reason  : Explicitly installed",
+        ])],
+    );
+    server.packages.insert(
+        "nano".to_string(),
+        vec![raw(&["Repository      : core", "Name            : nano", "Version         : 8.0-1", "Installed       : True"])],
+    );
+
+    let local = "\
+Name            : vim
+Version         : 9.1-1
+Required By     : gvim
+Optional For    : None
+Install Reason  : Installed as a dependency for another package
+
+Name            : yay
+Version         : 12.4.2-1
+Required By     : None
+Optional For    : None
+Install Reason  : Explicitly installed
+
+Name            : vim-runtime
+Version         : 9.1-1
+Required By     : None
+Optional For    : None
+Install Reason  : Installed as a dependency for another package
+";
+    let installed = server.installed_from(local.to_string());
+    assert_eq!(installed.len(), 3);
+    assert_eq!(installed[0].get_property("Version".to_string()), Some("9.1-1".to_string()));
+    assert_eq!(installed[0].get_property("Repository".to_string()), Some("extra".to_string()));
+    assert!(installed[0].get_property("Install Reason".to_string()).unwrap().starts_with("Installed as a dependency"));
+    assert!(installed[1].is_installed());
+    assert_eq!(installed[1].get_property("Name".to_string()), Some("yay".to_string()));
+    let orphans = installed.iter().filter(|p| p.get_property("Orphan".to_string()).is_some()).collect::<Vec<_>>();
+    assert_eq!(orphans.len(), 1);
+    assert_eq!(orphans[0].get_property("Name".to_string()), Some("vim-runtime".to_string()));
+
+    //nano was removed since the flags were last refreshed
+    let packages = server.with_sync_entries(installed);
+    assert_eq!(packages.len(), 4);
+    assert!(!packages[3].is_installed());
+    assert_eq!(packages[3].get_property("Name".to_string()), Some("nano".to_string()));
+}
@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::logic::export::ExportColumn;
+use crate::logic::hold;
+use crate::logic::package::Package;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    pub name: String,
+    pub version: String,
+    //"explicit" or "dependency", like the export
+    pub reason: String,
+}
+
+//The installed set at one point in time
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    //"YYYY-MM-DD HH:MM:SS", local time
+    pub created: String,
+    pub packages: Vec<SnapshotEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryChange {
+    pub name: String,
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapshotDiff {
+    pub added: Vec<SnapshotEntry>,
+    pub removed: Vec<SnapshotEntry>,
+    pub versions: Vec<EntryChange>,
+    pub reasons: Vec<EntryChange>,
+}
+
+pub fn snapshot_dir() -> PathBuf {
+    hold::hold_file().with_file_name("snapshots")
+}
+
+impl Snapshot {
+    //Takes the installed entries of `packages`, which should come from `Server::installed` so versions and reasons are current
+    pub fn capture(packages: &[Package], created: String) -> Snapshot {
+        let mut entries = packages
+            .iter()
+            .filter(|p| p.is_installed())
+            .map(|p| SnapshotEntry {
+                name: ExportColumn::Name.value(p),
+                version: ExportColumn::Version.value(p),
+                reason: ExportColumn::Reason.value(p),
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Snapshot { created, packages: entries }
+    }
+
+    //Changes that lead from `self` to `other`
+    pub fn diff(&self, other: &Snapshot) -> SnapshotDiff {
+        let mut diff = SnapshotDiff::default();
+        for entry in self.packages.iter() {
+            match other.packages.iter().find(|e| e.name == entry.name) {
+                None => diff.removed.push(entry.clone()),
+                Some(new) => {
+                    if new.version != entry.version {
+                        diff.versions.push(EntryChange {
+                            name: entry.name.clone(),
+                            old: entry.version.clone(),
+                            new: new.version.clone(),
+                        });
+                    }
+                    if new.reason != entry.reason {
+                        diff.reasons.push(EntryChange {
+                            name: entry.name.clone(),
+                            old: entry.reason.clone(),
+                            new: new.reason.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        diff.added = other
+            .packages
+            .iter()
+            .filter(|e| !self.packages.iter().any(|x| x.name == e.name))
+            .cloned()
+            .collect();
+        diff
+    }
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.versions.is_empty() && self.reasons.is_empty()
+    }
+}
+
+//Written as "<dir>/2024-05-01_10-00-00.json", so the file names sort by date
+pub fn save(dir: &Path, snapshot: &Snapshot) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!("{}.json", snapshot.created.replace(' ', "_").replace(':', "-")));
+    let content = serde_json::to_string_pretty(snapshot).map_err(|e| e.to_string())?;
+    std::fs::write(&path, content).map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+pub fn load(path: &Path) -> Result<Snapshot, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid snapshot {}: {}", path.display(), e))
+}
+
+//Saved snapshots, oldest first
+pub fn list(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut paths = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "json"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn test_snapshot() {
+    use crate::logic::server::Server;
+
+    let raw = |lines: &[&str]| Package::from_raw(lines.iter().map(|x| x.to_string()).collect());
+    let before = Snapshot::capture(
+        &[
+            raw(&["Name            : vim", "Version         : 9.0-1", "Installed       : True", "Install Reason  : Explicitly installed"]),
+            raw(&[
+                "Name            : glibc",
+                "Version         : 2.39-1",
+                "Installed       : True",
+                "Install Reason  : Installed as a dependency for another package",
+            ]),
+            raw(&["Name            : nano", "Version         : 8.0-1", "Installed       : True", "Install Reason  : Explicitly installed"]),
+            raw(&["Name            : git", "Version         : 2.45-1", "Installed       : False"]),
+        ],
+        "2024-05-01 10:00:00".to_string(),
+    );
+    assert_eq!(before.packages.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(), vec!["glibc", "nano", "vim"]);
+    assert_eq!(before.packages[0].reason, "dependency");
+
+    let mut after = before.clone();
+    after.packages.retain(|e| e.name != "nano");
+    after.packages[0].reason = "explicit".to_string();
+    after.packages[1].version = "9.1-1".to_string();
+    after.packages.push(SnapshotEntry {
+        name: "git".to_string(),
+        version: "2.45-1".to_string(),
+        reason: "explicit".to_string(),
+    });
+
+    let diff = before.diff(&after);
+    assert_eq!(diff.added.len(), 1);
+    assert_eq!(diff.removed[0].name, "nano");
+    assert_eq!(
+        diff.versions,
+        vec![EntryChange {
+            name: "vim".to_string(),
+            old: "9.0-1".to_string(),
+            new: "9.1-1".to_string(),
+        }]
+    );
+    assert_eq!(diff.reasons[0].name, "glibc");
+    assert!(before.diff(&before).is_empty());
+
+    //After an upgrade the live side is read from pacman again, not from the state loaded at startup
+    let live = Server::default().installed_from(
+        "Name            : vim\nVersion         : 9.1-1\nInstall Reason  : Explicitly installed\n".to_string(),
+    );
+    let upgraded = Snapshot::capture(&live, "Live system".to_string());
+    assert_eq!(
+        before.diff(&upgraded).versions,
+        vec![EntryChange {
+            name: "vim".to_string(),
+            old: "9.0-1".to_string(),
+            new: "9.1-1".to_string(),
+        }]
+    );
+
+    let dir = std::env::temp_dir().join(format!("pacmanager-snapshots-{}", std::process::id()));
+    let path = save(&dir, &before).unwrap();
+    assert_eq!(path.file_name().unwrap(), "2024-05-01_10-00-00.json");
+    assert_eq!(list(&dir), vec![path.clone()]);
+    assert_eq!(load(&path).unwrap(), before);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,284 @@
+use std::path::Path;
+use std::process::Command;
+
+use crate::logic::lock::{self, LockState};
+use crate::logic::pacman_conf::{PACMAN_CONF, PacmanConf};
+use crate::logic::privileged;
+
+//A batch of pacman operations that is previewed first, the pacman invocations then run under a single pkexec prompt
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transaction {
+    pub description: String,
+    //Extra lines shown with the preview, such as the metadata of local archives
+    pub notes: Vec<String>,
+    //Sync targets, "repo/name" is accepted to pin a repository
+    pub install: Vec<String>,
+    //Paths of local package archives, installed with `pacman -U`
+    pub install_files: Vec<String>,
+    pub remove: Vec<String>,
+    //Names added to IgnorePkg once the packages are installed, so a downgrade is not undone by the next update
+    pub ignore: Vec<String>,
+    //Repositories enabled in pacman.conf before anything else, followed by a database refresh
+    pub repositories: Vec<String>,
+    //Providers picked for virtual dependencies, installed as dependencies ahead of `install`
+    pub providers: Vec<String>,
+    //Names among the installed targets that are marked as dependencies afterwards
+    pub dependencies: Vec<String>,
+    //Questions whose default answer is inverted, a bitmask of the ASK_ constants passed as pacman's --ask
+    pub ask: u32,
+    //Removes after everything else is installed instead of first, an undo has to downgrade a package
+    //before what only its newer version depended on can go
+    pub remove_last: bool,
+}
+
+//Values of alpm's question types, as taken by --ask
+pub const ASK_CONFLICT: u32 = 1 << 2;
+pub const ASK_IMPORT_KEY: u32 = 1 << 6;
+
+//What pacman resolved the transaction to, as "name version" entries
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionPreview {
+    pub install: Vec<String>,
+    pub remove: Vec<String>,
+}
+
+//One part of `commit`: pacman.conf replaced through `PacmanConf::save`, or pacman invocations chained under one prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    WriteConf(PacmanConf),
+    Run(Vec<String>),
+}
+
+//Wraps an argument in single quotes so it survives `sh -c`
+pub fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+fn quote_all(args: &[String]) -> String {
+    args.iter()
+        .map(|x| shell_quote(x))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl Transaction {
+    pub fn is_empty(&self) -> bool {
+        self.install.is_empty()
+            && self.install_files.is_empty()
+            && self.remove.is_empty()
+            && self.ignore.is_empty()
+            && self.repositories.is_empty()
+    }
+
+    //The pacman invocations performed by `commit`, in order, without privilege escalation
+    pub fn commands(&self) -> Vec<String> {
+        let ask = if self.ask == 0 { String::new() } else { format!(" --ask {}", self.ask) };
+        let mut commands = vec![];
+        //The repositories enabled by `steps` have no database yet
+        if !self.repositories.is_empty() {
+            commands.push("pacman -Sy --noconfirm".to_string());
+        }
+        let remove = format!("pacman -R --noconfirm {}", quote_all(&self.remove));
+        if !self.remove.is_empty() && !self.remove_last {
+            commands.push(remove.clone());
+        }
+        if !self.install_files.is_empty() {
+            commands.push(format!("pacman -U --noconfirm{} {}", ask, quote_all(&self.install_files)));
+        }
+        if !self.providers.is_empty() {
+            commands.push(format!("pacman -S --needed --asdeps --noconfirm{} {}", ask, quote_all(&self.providers)));
+        }
+        if !self.install.is_empty() {
+            commands.push(format!("pacman -S --needed --noconfirm{} {}", ask, quote_all(&self.install)));
+        }
+        if !self.dependencies.is_empty() {
+            commands.push(format!("pacman -D --asdeps {}", quote_all(&self.dependencies)));
+        }
+        if !self.remove.is_empty() && self.remove_last {
+            commands.push(remove);
+        }
+        commands
+    }
+
+    //Everything `commit` does starting from `conf`: repositories are enabled before the pacman invocations
+    //and IgnorePkg is extended after them. Both edits are validated up front so nothing runs if one is broken
+    pub fn steps(&self, conf: &PacmanConf) -> Result<Vec<Step>, String> {
+        let mut steps = vec![];
+        let mut conf = conf.clone();
+        if !self.repositories.is_empty() {
+            self.repositories.iter().for_each(|r| conf.set_repository_enabled(r, true));
+            conf.validate()?;
+            steps.push(Step::WriteConf(conf.clone()));
+        }
+        let commands = self.commands();
+        if !commands.is_empty() {
+            steps.push(Step::Run(commands));
+        }
+        if !self.ignore.is_empty() {
+            conf.add_values("IgnorePkg", &self.ignore);
+            conf.validate()?;
+            steps.push(Step::WriteConf(conf));
+        }
+        Ok(steps)
+    }
+
+    //Resolves dependencies without touching the system, `--print` does not need root
+    pub fn preview(&self) -> Result<TransactionPreview, String> {
+        let mut preview = TransactionPreview::default();
+
+        if !self.remove.is_empty() {
+            //The dependency check only holds once the rest is installed, so it is left to the commit
+            let flags: &[&str] = if self.remove_last { &["-Rdd"] } else { &["-R"] };
+            preview.remove = Self::print(flags, &self.remove)?;
+        }
+        if !self.install_files.is_empty() {
+            preview.install.extend(Self::print(&["-U"], &self.install_files)?);
+        }
+        if !self.install.is_empty() {
+            let targets = self.providers.iter().chain(self.install.iter()).cloned().collect::<Vec<_>>();
+            //Repositories enabled by the transaction have no database yet, their packages can not be resolved
+            if self.repositories.is_empty() {
+                preview.install.extend(Self::print(&["-S", "--needed"], &targets)?);
+            } else {
+                preview.install.extend(targets);
+            }
+        }
+
+        Ok(preview)
+    }
+
+    fn print(operation: &[&str], targets: &[String]) -> Result<Vec<String>, String> {
+        let output = Command::new("pacman")
+            .args(operation)
+            .args(["--print", "--print-format", "%n %v"])
+            .args(targets)
+            .output()
+            .map_err(|e| e.to_string())?;
+
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).to_string());
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|x| !x.trim().is_empty())
+            .map(|x| x.to_string())
+            .collect())
+    }
+
+    //Runs the pacman invocations under one pkexec prompt, pacman.conf edits are written like the editor does.
+    //Returns stderr like the other operations, a failing step stops the ones after it
+    pub fn commit(&self) -> String {
+        self.commit_checked().unwrap_or_else(|e| e)
+    }
+
+    //Like `commit`, telling success apart from failure: pacman also writes warnings to stderr on success,
+    //they are what `Ok` carries
+    pub fn commit_checked(&self) -> Result<String, String> {
+        //Reported before asking for a password, pacman's own "unable to lock database" is opaque
+        let lock = lock::check(&lock::lock_file(), Path::new(lock::PROC_PATH));
+        if lock != LockState::Free {
+            return Err(lock.describe());
+        }
+        //pacman.conf is only read when it is rewritten
+        let conf = if self.repositories.is_empty() && self.ignore.is_empty() {
+            PacmanConf::default()
+        } else {
+            PacmanConf::load(Path::new(PACMAN_CONF))?
+        };
+
+        let mut stderror = String::new();
+        for step in self.steps(&conf)? {
+            match step {
+                Step::WriteConf(conf) => {
+                    let e = conf.save(Path::new(PACMAN_CONF));
+                    if !e.is_empty() {
+                        return Err(stderror + &e);
+                    }
+                }
+                Step::Run(commands) => {
+                    let (success, e) = privileged::run_status(commands.join(" && "));
+                    stderror += &e;
+                    if !success {
+                        return Err(stderror);
+                    }
+                }
+            }
+        }
+        Ok(stderror)
+    }
+}
+
+#[test]
+fn test_transaction_commands() {
+    let transaction = Transaction {
+        description: "test".to_string(),
+        notes: vec![],
+        install: vec!["extra/vim".to_string(), "gcc".to_string()],
+        install_files: vec!["/tmp/it's.pkg.tar.zst".to_string()],
+        remove: vec!["nano".to_string()],
+        ignore: vec![],
+        repositories: vec![],
+        providers: vec![],
+        dependencies: vec![],
+        ask: 0,
+        remove_last: false,
+    };
+
+    assert_eq!(
+        transaction.commands(),
+        vec![
+            "pacman -R --noconfirm 'nano'".to_string(),
+            "pacman -U --noconfirm '/tmp/it'\\''s.pkg.tar.zst'".to_string(),
+            "pacman -S --needed --noconfirm 'extra/vim' 'gcc'".to_string(),
+        ]
+    );
+    assert!(Transaction::default().is_empty());
+
+    let transaction = Transaction {
+        install: vec!["ardour".to_string()],
+        providers: vec!["pipewire-jack".to_string()],
+        ask: ASK_CONFLICT,
+        ..Transaction::default()
+    };
+    assert_eq!(
+        transaction.commands(),
+        vec![
+            "pacman -S --needed --asdeps --noconfirm --ask 4 'pipewire-jack'".to_string(),
+            "pacman -S --needed --noconfirm --ask 4 'ardour'".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn test_transaction_steps() {
+    let conf = PacmanConf::parse("[options]\n#IgnorePkg   =\n[core]\nInclude = /etc/pacman.d/mirrorlist\n#[multilib]\n#Include = /etc/pacman.d/mirrorlist\n");
+    let transaction = Transaction {
+        install: vec!["steam".to_string()],
+        ignore: vec!["linux".to_string()],
+        repositories: vec!["multilib".to_string()],
+        ..Transaction::default()
+    };
+
+    let steps = transaction.steps(&conf).unwrap();
+    assert_eq!(steps.len(), 3);
+    let Step::WriteConf(enabled) = &steps[0] else {
+        panic!("the repositories are not enabled first");
+    };
+    assert!(enabled.repositories().iter().all(|r| r.enabled));
+    assert!(enabled.values("options", "IgnorePkg").is_empty());
+    assert_eq!(
+        steps[1],
+        Step::Run(vec!["pacman -Sy --noconfirm".to_string(), "pacman -S --needed --noconfirm 'steam'".to_string()])
+    );
+    let Step::WriteConf(held) = &steps[2] else {
+        panic!("IgnorePkg is not written last");
+    };
+    assert_eq!(held.values("options", "IgnorePkg"), vec!["linux".to_string()]);
+    assert!(held.repositories().iter().all(|r| r.enabled));
+
+    //An edit pacman would reject is refused before anything runs
+    let broken = PacmanConf::parse("[options]\n#[multilib]\n");
+    assert!(transaction.steps(&broken).is_err());
+    assert_eq!(Transaction { ignore: vec!["vim".to_string()], ..Transaction::default() }.steps(&conf).unwrap().len(), 1);
+}
@@ -0,0 +1,128 @@
+use std::cmp::Ordering;
+
+//Port of libalpm's `alpm_pkg_vercmp`, the logic behind the `vercmp` tool
+
+//Splits "epoch:version-release", a missing epoch counts as 0
+fn parse_evr(evr: &str) -> (&str, &str, Option<&str>) {
+    let (epoch, rest) = match evr.split_once(':') {
+        Some((e, rest)) if e.chars().all(|c| c.is_ascii_digit()) => (if e.is_empty() { "0" } else { e }, rest),
+        _ => ("0", evr),
+    };
+    match rest.rsplit_once('-') {
+        Some((version, release)) => (epoch, version, Some(release)),
+        None => (epoch, rest, None),
+    }
+}
+
+//rpm style comparison of alternating numeric and alphabetic segments
+fn rpmvercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+    let (mut one, mut two) = (0, 0);
+
+    while one < a.len() && two < b.len() {
+        let (start1, start2) = (one, two);
+        while one < a.len() && !a[one].is_ascii_alphanumeric() {
+            one += 1;
+        }
+        while two < b.len() && !b[two].is_ascii_alphanumeric() {
+            two += 1;
+        }
+        if one >= a.len() || two >= b.len() {
+            break;
+        }
+        //A different amount of separators decides on its own
+        if one - start1 != two - start2 {
+            return (one - start1).cmp(&(two - start2));
+        }
+
+        let (mut end1, mut end2) = (one, two);
+        let is_num = a[one].is_ascii_digit();
+        if is_num {
+            while end1 < a.len() && a[end1].is_ascii_digit() {
+                end1 += 1;
+            }
+            while end2 < b.len() && b[end2].is_ascii_digit() {
+                end2 += 1;
+            }
+        } else {
+            while end1 < a.len() && a[end1].is_ascii_alphabetic() {
+                end1 += 1;
+            }
+            while end2 < b.len() && b[end2].is_ascii_alphabetic() {
+                end2 += 1;
+            }
+        }
+
+        //Segments of different types, numbers are newer than letters
+        if end2 == two {
+            return if is_num { Ordering::Greater } else { Ordering::Less };
+        }
+
+        let mut seg1 = &a[one..end1];
+        let mut seg2 = &b[two..end2];
+        if is_num {
+            while seg1.len() > 1 && seg1[0] == b'0' {
+                seg1 = &seg1[1..];
+            }
+            while seg2.len() > 1 && seg2[0] == b'0' {
+                seg2 = &seg2[1..];
+            }
+            if seg1.len() != seg2.len() {
+                return seg1.len().cmp(&seg2.len());
+            }
+        }
+        match seg1.cmp(seg2) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+
+        one = end1;
+        two = end2;
+    }
+
+    if one >= a.len() && two >= b.len() {
+        return Ordering::Equal;
+    }
+
+    //"1.0" is older than "1.0.1" but newer than "1.0rc1"
+    if (one >= a.len() && !b[two].is_ascii_alphabetic()) || (one < a.len() && a[one].is_ascii_alphabetic()) {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    }
+}
+
+pub fn vercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+
+    let (epoch1, version1, release1) = parse_evr(a);
+    let (epoch2, version2, release2) = parse_evr(b);
+
+    rpmvercmp(epoch1, epoch2)
+        .then_with(|| rpmvercmp(version1, version2))
+        .then_with(|| match (release1, release2) {
+            (Some(r1), Some(r2)) => rpmvercmp(r1, r2),
+            _ => Ordering::Equal,
+        })
+}
+
+#[test]
+fn test_vercmp() {
+    assert_eq!(vercmp("1.0-1", "1.0-1"), Ordering::Equal);
+    assert_eq!(vercmp("1.0-1", "1.0-2"), Ordering::Less);
+    assert_eq!(vercmp("1.0", "1.0-2"), Ordering::Equal);
+    assert_eq!(vercmp("1.10", "1.9"), Ordering::Greater);
+    assert_eq!(vercmp("1.0", "1.0.1"), Ordering::Less);
+    assert_eq!(vercmp("1.0rc1", "1.0"), Ordering::Less);
+    assert_eq!(vercmp("1.0a", "1.0b"), Ordering::Less);
+    assert_eq!(vercmp("1:1.0", "2.0"), Ordering::Greater);
+    assert_eq!(vercmp("1.001", "1.1"), Ordering::Equal);
+    assert_eq!(vercmp("2.4.1-1", "2.4.1.r3.g1a2b3c-1"), Ordering::Less);
+}
@@ -1,16 +1,31 @@
 use std::sync::{Arc, Mutex};
 
+mod cli;
 mod logic;
 mod ui;
 
 use iced::{
-    widget::row, Application, Task
+    widget::{button, column, row, text}, Application, Task
 };
 use logic::server::Server;
 use ui::{
+    cache::{CacheMessage, CacheView},
+    config_files::{ConfigFilesMessage, ConfigFilesView},
+    groups::{GroupBrowser, GroupMessage},
+    history::{HistoryMessage, HistoryView},
+    integrity::{IntegrityMessage, IntegrityView},
+    keyring::{KeyringMessage, KeyringView},
+    lock::{LockMessage, LockView},
+    mirrors::{MirrorsMessage, MirrorsView},
     package_button::PackageCardMessage,
     package_display::{PackageDisplay, PackageViewMessage},
+    pacman_conf::{PacmanConfMessage, PacmanConfView},
+    profile::{ProfileMessage, ProfileView},
+    reconcile::{ReconcileMessage, ReconcileView},
+    repositories::{RepositoryBrowser, RepositoryMessage},
     search::{SearchMessage, SearchWidget},
+    snapshots::{SnapshotMessage, SnapshotsView},
+    transaction::{TransactionMessage, TransactionView},
 };
 
 #[derive(Debug, Clone)]
@@ -18,14 +33,63 @@ enum AppMessage {
     SearchMessage(SearchMessage),
     PackageCardMessage(PackageCardMessage),
     PackageViewMessage(PackageViewMessage),
+    RepositoryMessage(RepositoryMessage),
+    GroupMessage(GroupMessage),
+    IntegrityMessage(IntegrityMessage),
+    ConfigFilesMessage(ConfigFilesMessage),
+    CacheMessage(CacheMessage),
+    HistoryMessage(HistoryMessage),
+    PacmanConfMessage(PacmanConfMessage),
+    MirrorsMessage(MirrorsMessage),
+    KeyringMessage(KeyringMessage),
+    ReconcileMessage(ReconcileMessage),
+    ProfileMessage(ProfileMessage),
+    SnapshotMessage(SnapshotMessage),
+    TransactionMessage(TransactionMessage),
+    LockMessage(LockMessage),
+    TabSelected(Tab),
     ForceUpdate
 }
 
+//Views that can be shown on the left side, the package display is always visible
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Tab {
+    #[default]
+    Search,
+    Repositories,
+    Groups,
+    Integrity,
+    ConfigFiles,
+    Cache,
+    History,
+    PacmanConf,
+    Mirrors,
+    Keyring,
+    Reconcile,
+    Profile,
+    Snapshots,
+}
+
 #[derive(Clone, Debug)]
 struct MainUI {
     server: Arc<Mutex<Server>>,
     search: SearchWidget,
+    repositories: RepositoryBrowser,
+    groups: GroupBrowser,
+    integrity: IntegrityView,
+    config_files: ConfigFilesView,
+    cache: CacheView,
+    history: HistoryView,
+    pacman_conf: PacmanConfView,
+    mirrors: MirrorsView,
+    keyring: KeyringView,
+    reconcile: ReconcileView,
+    profile: ProfileView,
+    snapshots: SnapshotsView,
     view: PackageDisplay,
+    transaction: TransactionView,
+    lock: LockView,
+    tab: Tab,
 }
 
 impl Default for MainUI {
@@ -37,11 +101,49 @@ impl Default for MainUI {
                 server: Arc::new(Mutex::new(server.clone())),
                 ..Default::default()
             },
+            repositories: RepositoryBrowser {
+                server: Arc::new(Mutex::new(server.clone())),
+                ..Default::default()
+            },
+            groups: GroupBrowser {
+                server: Arc::new(Mutex::new(server.clone())),
+                ..Default::default()
+            },
+            integrity: IntegrityView::default(),
+            config_files: ConfigFilesView::default(),
+            cache: CacheView {
+                server: Arc::new(Mutex::new(server.clone())),
+                ..Default::default()
+            },
+            history: HistoryView {
+                server: Arc::new(Mutex::new(server.clone())),
+                ..Default::default()
+            },
+            pacman_conf: PacmanConfView::default(),
+            mirrors: MirrorsView::default(),
+            keyring: KeyringView::default(),
+            reconcile: ReconcileView {
+                server: Arc::new(Mutex::new(server.clone())),
+                ..Default::default()
+            },
+            profile: ProfileView {
+                server: Arc::new(Mutex::new(server.clone())),
+                ..Default::default()
+            },
+            snapshots: SnapshotsView {
+                server: Arc::new(Mutex::new(server.clone())),
+                ..Default::default()
+            },
             view: PackageDisplay {
                 server: Arc::new(Mutex::new(server.clone())),
-                package: None,
-                loading: false
+                ..Default::default()
+            },
+            transaction: TransactionView {
+                server: Arc::new(Mutex::new(server.clone())),
+                ..Default::default()
             },
+            lock: LockView::default(),
+            tab: Tab::Search,
         };
         return val;
     }
@@ -53,14 +155,103 @@ impl MainUI {
 			theme
 		},  |_| AppMessage::ForceUpdate);
 
-        Task::batch(vec![self.view.update(message.clone()), self.search.update(message.clone()), theme_task])
+        let tab_task = match message {
+            AppMessage::TabSelected(tab) => {
+                self.tab = tab;
+                match tab {
+                    Tab::Repositories => Task::done(AppMessage::RepositoryMessage(RepositoryMessage::Load)),
+                    Tab::Groups => Task::done(AppMessage::GroupMessage(GroupMessage::Load)),
+                    Tab::ConfigFiles => Task::done(AppMessage::ConfigFilesMessage(ConfigFilesMessage::Scan)),
+                    Tab::Cache => Task::done(AppMessage::CacheMessage(CacheMessage::Scan)),
+                    Tab::History => Task::done(AppMessage::HistoryMessage(HistoryMessage::Load)),
+                    Tab::PacmanConf => Task::done(AppMessage::PacmanConfMessage(PacmanConfMessage::Load)),
+                    Tab::Mirrors => Task::done(AppMessage::MirrorsMessage(MirrorsMessage::Load)),
+                    Tab::Keyring => Task::done(AppMessage::KeyringMessage(KeyringMessage::Load)),
+                    Tab::Profile => Task::done(AppMessage::ProfileMessage(ProfileMessage::Check)),
+                    Tab::Snapshots => Task::done(AppMessage::SnapshotMessage(SnapshotMessage::Load)),
+                    _ => Task::none(),
+                }
+            }
+            _ => Task::none(),
+        };
+
+        Task::batch(vec![
+            self.view.update(message.clone()),
+            self.search.update(message.clone()),
+            self.repositories.update(message.clone()),
+            self.groups.update(message.clone()),
+            self.integrity.update(message.clone()),
+            self.config_files.update(message.clone()),
+            self.cache.update(message.clone()),
+            self.history.update(message.clone()),
+            self.pacman_conf.update(message.clone()),
+            self.mirrors.update(message.clone()),
+            self.keyring.update(message.clone()),
+            self.reconcile.update(message.clone()),
+            self.profile.update(message.clone()),
+            self.snapshots.update(message.clone()),
+            self.transaction.update(message.clone()),
+            self.lock.update(message.clone()),
+            theme_task,
+            tab_task,
+        ])
 
     }
 
     fn view(&self) -> iced::widget::Row<AppMessage> {
+        let tab_button = |label: &str, tab: Tab| {
+            button(text(label.to_string()))
+                .style(if self.tab == tab { button::primary } else { button::secondary })
+                .on_press(AppMessage::TabSelected(tab))
+        };
+
+        let tabs = row![
+            tab_button("Search", Tab::Search),
+            tab_button("Repositories", Tab::Repositories),
+            tab_button("Groups", Tab::Groups),
+            tab_button("Integrity", Tab::Integrity),
+            tab_button("Config files", Tab::ConfigFiles),
+            tab_button("Cache", Tab::Cache),
+            tab_button("History", Tab::History),
+            tab_button("pacman.conf", Tab::PacmanConf),
+            tab_button("Mirrors", Tab::Mirrors),
+            tab_button("Keyring", Tab::Keyring),
+            tab_button("Import list", Tab::Reconcile),
+            tab_button(&self.profile.tab_label(), Tab::Profile),
+            tab_button("Snapshots", Tab::Snapshots),
+        ]
+        .spacing(10)
+        .wrap();
+
+        let content = match self.tab {
+            Tab::Search => self.search.view(),
+            Tab::Repositories => self.repositories.view(),
+            Tab::Groups => self.groups.view(),
+            Tab::Integrity => self.integrity.view(),
+            Tab::ConfigFiles => self.config_files.view(),
+            Tab::Cache => self.cache.view(),
+            Tab::History => self.history.view(),
+            Tab::PacmanConf => self.pacman_conf.view(),
+            Tab::Mirrors => self.mirrors.view(),
+            Tab::Keyring => self.keyring.view(),
+            Tab::Reconcile => self.reconcile.view(),
+            Tab::Profile => self.profile.view(),
+            Tab::Snapshots => self.snapshots.view(),
+        };
+
+        //A pending transaction takes over the package display until it is confirmed or cancelled,
+        //an operation waiting for the database lock takes over both
+        let side = if self.lock.pending.is_some() {
+            self.lock.view()
+        } else if self.transaction.pending.is_some() {
+            self.transaction.view()
+        } else {
+            self.view.view()
+        };
+
         return row![
-            self.search.view().width(iced::Length::Fill),
-            self.view.view().width(iced::Length::Fill)
+            column![tabs, content].spacing(10).width(iced::Length::Fill),
+            side.width(iced::Length::Fill)
         ]
         .padding(20)
         .spacing(20);
@@ -68,13 +259,22 @@ impl MainUI {
 }
 
 fn main() -> iced::Result {
+    //Any argument selects the headless mode
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args));
+    }
+
     //TODO: Use mutex instead of RefCell
     let app = iced::application("Pacmanager", MainUI::update, MainUI::view).theme(theme);
 
 
     let state = MainUI::default();
 
-    app.run_with( || (state, Task::batch(vec![Task::done(AppMessage::SearchMessage(SearchMessage::SearchSubmited))]) ))
+    app.run_with( || (state, Task::batch(vec![
+        Task::done(AppMessage::SearchMessage(SearchMessage::SearchSubmited)),
+        Task::done(AppMessage::ProfileMessage(ProfileMessage::Check)),
+    ]) ))
 }
 
 fn theme(state: &MainUI) -> iced::Theme {
@@ -0,0 +1,175 @@
+use iced::Task;
+use iced::widget::{Column, button, column, row, scrollable, text};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::AppMessage;
+use crate::logic::cache::{self, CacheGroup, CachedPackage, CleanupPolicy};
+use crate::logic::files::format_size;
+use crate::logic::pacman_conf::PACMAN_CONF;
+use crate::logic::server::Server;
+
+//Shows the package cache grouped by name and cleans it with paccache style policies
+#[derive(Debug, Clone)]
+pub struct CacheView {
+    pub server: Arc<Mutex<Server>>,
+    //Cache directories separated by spaces, read from pacman.conf by default
+    pub dirs: String,
+    pub groups: Vec<CacheGroup>,
+    //Installed package names, refreshed on every scan
+    pub installed: HashSet<String>,
+    pub policy: CleanupPolicy,
+    pub loading: bool,
+}
+
+impl Default for CacheView {
+    fn default() -> Self {
+        CacheView {
+            server: Default::default(),
+            dirs: cache::cache_dirs(Path::new(PACMAN_CONF))
+                .iter()
+                .map(|d| d.to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            groups: vec![],
+            installed: HashSet::new(),
+            policy: CleanupPolicy::default(),
+            loading: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum CacheMessage {
+    DirsChanged(String),
+    Scan,
+    Scanned(Vec<CacheGroup>),
+    KeepChanged(usize),
+    OnlyUninstalledToggled(bool),
+    Clean,
+    Cleaned(String),
+}
+
+impl CacheView {
+    fn plan(&self) -> Vec<CachedPackage> {
+        cache::plan_cleanup(&self.groups, self.policy, &self.installed)
+    }
+
+    pub fn update(&mut self, message: AppMessage) -> Task<AppMessage> {
+        match message {
+            AppMessage::CacheMessage(m) => match m {
+                CacheMessage::DirsChanged(dirs) => {
+                    self.dirs = dirs;
+                    Task::none()
+                }
+                CacheMessage::Scan => {
+                    self.loading = true;
+                    self.installed = self
+                        .server
+                        .lock()
+                        .unwrap()
+                        .installed()
+                        .iter()
+                        .filter_map(|p| p.get_property("Name".to_string()))
+                        .collect();
+                    let dirs = self.dirs.split_whitespace().map(PathBuf::from).collect::<Vec<_>>();
+                    Task::perform(async move { cache::scan(&dirs) }, |g| {
+                        AppMessage::CacheMessage(CacheMessage::Scanned(g))
+                    })
+                }
+                CacheMessage::Scanned(groups) => {
+                    self.groups = groups;
+                    self.loading = false;
+                    Task::none()
+                }
+                CacheMessage::KeepChanged(keep) => {
+                    self.policy.keep = keep;
+                    Task::none()
+                }
+                CacheMessage::OnlyUninstalledToggled(b) => {
+                    self.policy.only_uninstalled = b;
+                    Task::none()
+                }
+                CacheMessage::Clean => {
+                    let plan = self.plan();
+                    self.loading = true;
+                    Task::perform(async move { cache::remove(&plan) }, |stderror| {
+                        AppMessage::CacheMessage(CacheMessage::Cleaned(stderror))
+                    })
+                }
+                CacheMessage::Cleaned(stderror) => {
+                    self.loading = false;
+                    if !stderror.is_empty() {
+                        let _ = native_dialog::MessageDialog::new().set_text(&stderror).set_title("An error has ocurred :(").show_alert();
+                    }
+                    self.update(AppMessage::CacheMessage(CacheMessage::Scan))
+                }
+            },
+            _ => Task::none(),
+        }
+    }
+
+    pub fn view(&self) -> Column<'_, AppMessage> {
+        let total = self.groups.iter().map(|g| g.total_size()).sum::<u64>();
+        let plan = self.plan();
+        let freed = plan.iter().map(|p| p.size).sum::<u64>();
+
+        let groups = scrollable(
+            column(self.groups.iter().map(|g| {
+                column![
+                    row![
+                        text(g.name.clone()),
+                        iced::widget::horizontal_space(),
+                        text(format!("{} version(s), {}", g.versions.len(), format_size(g.total_size()))),
+                    ],
+                    text(
+                        g.versions
+                            .iter()
+                            .map(|v| v.version.clone())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                    .size(12),
+                ]
+                .into()
+            }))
+            .spacing(8),
+        )
+        .height(iced::Length::FillPortion(2));
+
+        let removal = scrollable(column(plan.iter().map(|p| {
+            text(format!("{} {} ({})", p.name, p.version, format_size(p.size))).size(12).into()
+        })))
+        .height(iced::Length::FillPortion(1));
+
+        column![
+            row![
+                iced::widget::text_input("cache directories", &self.dirs)
+                    .on_input(|x| AppMessage::CacheMessage(CacheMessage::DirsChanged(x)))
+                    .on_submit(AppMessage::CacheMessage(CacheMessage::Scan)),
+                button("Scan").on_press(AppMessage::CacheMessage(CacheMessage::Scan)),
+            ]
+            .spacing(10),
+            text(format!("{} package(s) cached, {}", self.groups.len(), format_size(total))),
+            groups,
+            row![
+                text("Keep"),
+                button("-").on_press(AppMessage::CacheMessage(CacheMessage::KeepChanged(self.policy.keep.saturating_sub(1)))),
+                text(self.policy.keep.to_string()),
+                button("+").on_press(AppMessage::CacheMessage(CacheMessage::KeepChanged(self.policy.keep + 1))),
+                text("version(s)"),
+                iced::widget::checkbox("Only uninstalled packages", self.policy.only_uninstalled)
+                    .on_toggle(|b| AppMessage::CacheMessage(CacheMessage::OnlyUninstalledToggled(b))),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            text(format!("{} file(s) would be removed, freeing {}", plan.len(), format_size(freed))),
+            removal,
+            button("Clean cache").on_press_maybe(
+                (!self.loading && !plan.is_empty()).then_some(AppMessage::CacheMessage(CacheMessage::Clean))
+            ),
+        ]
+        .spacing(10)
+    }
+}
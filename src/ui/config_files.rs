@@ -0,0 +1,213 @@
+use iced::Task;
+use iced::widget::{Column, button, column, row, scrollable, text};
+use std::path::Path;
+
+use crate::AppMessage;
+use crate::logic::files::DB_PATH;
+use crate::logic::pacnew::{self, CONFIG_ROOT, ConfigFile, ConfigKind, Hunk};
+
+//Lists .pacnew/.pacsave files, diffs them against the live file and resolves them
+#[derive(Debug, Clone)]
+pub struct ConfigFilesView {
+    pub root: String,
+    pub files: Vec<ConfigFile>,
+    pub selected: Option<usize>,
+    pub hunks: Vec<Hunk>,
+    //Which side of every changed hunk goes into a merge
+    pub use_new: Vec<bool>,
+    pub loading: bool,
+}
+
+impl Default for ConfigFilesView {
+    fn default() -> Self {
+        ConfigFilesView {
+            root: CONFIG_ROOT.to_string(),
+            files: vec![],
+            selected: None,
+            hunks: vec![],
+            use_new: vec![],
+            loading: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Keep,
+    Replace,
+    Merge,
+}
+
+#[derive(Debug, Clone)]
+pub enum ConfigFilesMessage {
+    RootChanged(String),
+    Scan,
+    Scanned(Vec<ConfigFile>),
+    Selected(usize),
+    HunkToggled(usize, bool),
+    Resolve(Resolution),
+    Resolved(String),
+}
+
+impl ConfigFilesView {
+    pub fn update(&mut self, message: AppMessage) -> Task<AppMessage> {
+        match message {
+            AppMessage::ConfigFilesMessage(m) => match m {
+                ConfigFilesMessage::RootChanged(root) => {
+                    self.root = root;
+                    Task::none()
+                }
+                ConfigFilesMessage::Scan => {
+                    self.loading = true;
+                    let root = self.root.clone();
+                    Task::perform(async move { pacnew::scan(Path::new(&root), Path::new(DB_PATH)) }, |files| {
+                        AppMessage::ConfigFilesMessage(ConfigFilesMessage::Scanned(files))
+                    })
+                }
+                ConfigFilesMessage::Scanned(files) => {
+                    self.loading = false;
+                    self.files = files;
+                    self.selected = None;
+                    self.hunks.clear();
+                    self.use_new.clear();
+                    Task::none()
+                }
+                ConfigFilesMessage::Selected(index) => {
+                    let Some(file) = self.files.get(index) else {
+                        return Task::none();
+                    };
+                    let (live, new) = file.read();
+                    self.hunks = pacnew::diff(&live, &new);
+                    self.use_new = self
+                        .hunks
+                        .iter()
+                        .filter(|h| matches!(h, Hunk::Changed { .. }))
+                        .map(|_| false)
+                        .collect();
+                    self.selected = Some(index);
+                    Task::none()
+                }
+                ConfigFilesMessage::HunkToggled(index, value) => {
+                    if let Some(v) = self.use_new.get_mut(index) {
+                        *v = value;
+                    }
+                    Task::none()
+                }
+                ConfigFilesMessage::Resolve(resolution) => {
+                    let Some(file) = self.selected.and_then(|i| self.files.get(i)).cloned() else {
+                        return Task::none();
+                    };
+                    let merged = pacnew::merge(&self.hunks, &self.use_new);
+                    self.loading = true;
+                    Task::perform(
+                        async move {
+                            match resolution {
+                                Resolution::Keep => file.keep(),
+                                Resolution::Replace => file.replace(),
+                                Resolution::Merge => file.merge(merged),
+                            }
+                        },
+                        |stderror| AppMessage::ConfigFilesMessage(ConfigFilesMessage::Resolved(stderror)),
+                    )
+                }
+                ConfigFilesMessage::Resolved(stderror) => {
+                    self.loading = false;
+                    if !stderror.is_empty() {
+                        let _ = native_dialog::MessageDialog::new().set_text(&stderror).set_title("An error has ocurred :(").show_alert();
+                    }
+                    self.update(AppMessage::ConfigFilesMessage(ConfigFilesMessage::Scan))
+                }
+            },
+            _ => Task::none(),
+        }
+    }
+
+    fn view_diff(&self) -> iced::Element<'_, AppMessage> {
+        let lines = |lines: &Vec<String>, style: fn(&iced::Theme) -> text::Style| {
+            column(lines.iter().map(move |l| text(l.clone()).font(iced::Font::MONOSPACE).style(style).into()))
+                .width(iced::Length::FillPortion(1))
+        };
+
+        let mut changed = 0;
+        let mut rows: Vec<iced::Element<AppMessage>> = vec![];
+        for hunk in self.hunks.iter() {
+            match hunk {
+                Hunk::Same(same) => rows.push(
+                    row![lines(same, text::default), lines(same, text::default)]
+                        .spacing(10)
+                        .into(),
+                ),
+                Hunk::Changed { live, new } => {
+                    let index = changed;
+                    changed += 1;
+                    rows.push(
+                        column![
+                            iced::widget::checkbox("Use new version", self.use_new[index]).on_toggle(
+                                move |b| AppMessage::ConfigFilesMessage(ConfigFilesMessage::HunkToggled(index, b))
+                            ),
+                            row![lines(live, text::danger), lines(new, text::success)].spacing(10),
+                        ]
+                        .spacing(2)
+                        .into(),
+                    )
+                }
+            }
+        }
+
+        scrollable(column(rows).spacing(5)).height(iced::Length::Fill).into()
+    }
+
+    pub fn view(&self) -> Column<'_, AppMessage> {
+        let files = column(self.files.iter().enumerate().map(|(index, f)| {
+            button(
+                row![
+                    text(f.path.to_string_lossy().to_string()),
+                    iced::widget::horizontal_space(),
+                    text(f.owner.clone().unwrap_or("no owner".to_string())),
+                    iced_aw::badge(match f.kind {
+                        ConfigKind::Pacnew => "pacnew",
+                        ConfigKind::Pacsave => "pacsave",
+                    })
+                    .style(iced_aw::style::badge::info),
+                ]
+                .spacing(10),
+            )
+            .style(if self.selected == Some(index) { button::primary } else { button::secondary })
+            .width(iced::Length::Fill)
+            .on_press(AppMessage::ConfigFilesMessage(ConfigFilesMessage::Selected(index)))
+            .into()
+        }))
+        .spacing(5);
+
+        let resolve = |label: &'static str, resolution: Resolution| {
+            button(label).on_press_maybe(
+                (!self.loading && self.selected.is_some())
+                    .then_some(AppMessage::ConfigFilesMessage(ConfigFilesMessage::Resolve(resolution))),
+            )
+        };
+
+        column![
+            row![
+                iced::widget::text_input("/etc", &self.root)
+                    .on_input(|x| AppMessage::ConfigFilesMessage(ConfigFilesMessage::RootChanged(x)))
+                    .on_submit(AppMessage::ConfigFilesMessage(ConfigFilesMessage::Scan)),
+                button("Scan").on_press_maybe((!self.loading).then_some(AppMessage::ConfigFilesMessage(ConfigFilesMessage::Scan))),
+            ]
+            .spacing(10),
+            text(format!("{} file(s) to review", self.files.len())),
+            scrollable(files).height(iced::Length::FillPortion(1)),
+            row![
+                text("Live file").width(iced::Length::FillPortion(1)),
+                text("New file").width(iced::Length::FillPortion(1)),
+            ],
+            column![self.view_diff()].height(iced::Length::FillPortion(2)),
+            row![
+                resolve("Keep live file", Resolution::Keep),
+                resolve("Replace with new", Resolution::Replace),
+                resolve("Apply merge", Resolution::Merge),
+            ]
+            .spacing(10),
+        ]
+        .spacing(10)
+    }
+}
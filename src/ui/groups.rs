@@ -0,0 +1,185 @@
+use iced::Task;
+use iced::widget::{Column, button, column, row, scrollable, text};
+use std::sync::{Arc, Mutex};
+
+use crate::AppMessage;
+use crate::logic::package::Package;
+use crate::logic::server::Server;
+use crate::logic::transaction::Transaction;
+use crate::ui::package_button::PackageButton;
+use crate::ui::transaction::TransactionMessage;
+
+#[derive(Debug, Clone)]
+pub struct GroupEntry {
+    pub name: String,
+    pub members: usize,
+    pub installed: usize,
+}
+
+//Lists package groups and installs a user chosen subset of a group, like pacman's group prompt
+#[derive(Default, Debug, Clone)]
+pub struct GroupBrowser {
+    pub server: Arc<Mutex<Server>>,
+    pub groups: Vec<GroupEntry>,
+    pub selected: Option<String>,
+    //Members of the selected group and whether they are part of the install
+    pub members: Vec<(Arc<Mutex<Package>>, bool)>,
+}
+
+#[derive(Debug, Clone)]
+pub enum GroupMessage {
+    Load,
+    Selected(String),
+    MemberToggled(usize, bool),
+    SelectAll(bool),
+    Install,
+}
+
+impl GroupBrowser {
+    pub fn update(&mut self, message: AppMessage) -> Task<AppMessage> {
+        match message {
+            AppMessage::GroupMessage(m) => match m {
+                GroupMessage::Load => {
+                    self.groups = self
+                        .server
+                        .lock()
+                        .unwrap()
+                        .get_groups()
+                        .into_iter()
+                        .map(|(name, members)| GroupEntry {
+                            name,
+                            members: members.len(),
+                            installed: members
+                                .iter()
+                                .filter(|p| p.lock().unwrap().is_installed())
+                                .count(),
+                        })
+                        .collect();
+                    match self.selected.clone() {
+                        Some(group) => self.update(AppMessage::GroupMessage(GroupMessage::Selected(group))),
+                        None => Task::none(),
+                    }
+                }
+                GroupMessage::Selected(group) => {
+                    let members = self
+                        .server
+                        .lock()
+                        .unwrap()
+                        .get_groups()
+                        .into_iter()
+                        .find(|(name, _)| *name == group)
+                        .map(|(_, members)| members)
+                        .unwrap_or_default();
+                    //Only members that are not installed yet are preselected, checking an installed one is harmless since --needed skips it
+                    self.members = members
+                        .into_iter()
+                        .map(|p| {
+                            let installed = p.lock().unwrap().is_installed();
+                            (p, !installed)
+                        })
+                        .collect();
+                    self.selected = Some(group);
+                    Task::none()
+                }
+                GroupMessage::MemberToggled(index, checked) => {
+                    if let Some(member) = self.members.get_mut(index) {
+                        member.1 = checked;
+                    }
+                    Task::none()
+                }
+                GroupMessage::SelectAll(checked) => {
+                    self.members.iter_mut().for_each(|m| m.1 = checked);
+                    Task::none()
+                }
+                GroupMessage::Install => {
+                    let install = self
+                        .members
+                        .iter()
+                        .filter(|(_, checked)| *checked)
+                        .map(|(p, _)| p.lock().unwrap().get_qualified_name())
+                        .collect::<Vec<_>>();
+                    Task::done(AppMessage::TransactionMessage(TransactionMessage::Request(
+                        Transaction {
+                            description: format!(
+                                "Install {} member(s) of {}",
+                                install.len(),
+                                self.selected.clone().unwrap_or_default()
+                            ),
+                            install,
+                            ..Default::default()
+                        },
+                    )))
+                }
+            },
+            _ => Task::none(),
+        }
+    }
+
+    pub fn view(&self) -> Column<'_, AppMessage> {
+        let groups = scrollable(
+            column(self.groups.iter().map(|g| {
+                let (state, style): (&str, fn(&iced::Theme, iced_aw::style::Status) -> iced_aw::style::badge::Style) =
+                    if g.installed == g.members {
+                        ("Installed", iced_aw::style::badge::success)
+                    } else if g.installed > 0 {
+                        ("Partial", iced_aw::style::badge::info)
+                    } else {
+                        ("Not installed", iced_aw::style::badge::warning)
+                    };
+                button(
+                    row![
+                        text(g.name.clone()),
+                        iced::widget::horizontal_space(),
+                        text(format!("{}/{} installed", g.installed, g.members)),
+                        iced_aw::badge(state).style(style),
+                    ]
+                    .spacing(10),
+                )
+                .style(if self.selected.as_ref() == Some(&g.name) {
+                    button::primary
+                } else {
+                    button::secondary
+                })
+                .width(iced::Length::Fill)
+                .on_press(AppMessage::GroupMessage(GroupMessage::Selected(g.name.clone())))
+                .into()
+            }))
+            .spacing(5),
+        )
+        .height(iced::Length::FillPortion(1));
+
+        let members = scrollable(
+            column(self.members.iter().enumerate().map(|(index, (package, checked))| {
+                row![
+                    iced::widget::checkbox("", *checked).on_toggle(move |c| {
+                        AppMessage::GroupMessage(GroupMessage::MemberToggled(index, c))
+                    }),
+                    PackageButton { package: package.clone() }.view()
+                ]
+                .spacing(5)
+                .align_y(iced::Alignment::Center)
+                .into()
+            }))
+            .spacing(5),
+        )
+        .height(iced::Length::FillPortion(2));
+
+        let selected_count = self.members.iter().filter(|(_, c)| *c).count();
+
+        column![
+            text(format!("{} group(s)", self.groups.len())),
+            groups,
+            row![
+                button("Select all").on_press(AppMessage::GroupMessage(GroupMessage::SelectAll(true))),
+                button("Select none").on_press(AppMessage::GroupMessage(GroupMessage::SelectAll(false))),
+                iced::widget::horizontal_space(),
+                button(text(format!("Install {} selected", selected_count))).on_press_maybe(
+                    (selected_count > 0).then_some(AppMessage::GroupMessage(GroupMessage::Install))
+                ),
+            ]
+            .spacing(10),
+            members
+        ]
+        .spacing(10)
+    }
+}
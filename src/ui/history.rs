@@ -0,0 +1,196 @@
+use iced::Task;
+use iced::widget::{Column, button, column, row, scrollable, text};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::AppMessage;
+use crate::logic::cache;
+use crate::logic::history::{self, HistoryEntry, HistoryFilter};
+use crate::logic::pacman_conf::PACMAN_CONF;
+use crate::logic::server::Server;
+use crate::ui::transaction::TransactionMessage;
+
+const PAGE_SIZE: usize = 50;
+
+//Transactions recorded in pacman.log, newest first, filtered by package and date range
+#[derive(Debug, Clone)]
+pub struct HistoryView {
+    pub server: Arc<Mutex<Server>>,
+    pub path: String,
+    pub entries: Vec<HistoryEntry>,
+    pub filter: HistoryFilter,
+    pub error: Option<String>,
+    pub loading: bool,
+    pub page: usize,
+}
+
+impl Default for HistoryView {
+    fn default() -> Self {
+        HistoryView {
+            server: Arc::default(),
+            path: history::log_path(Path::new(PACMAN_CONF)).to_string_lossy().to_string(),
+            entries: vec![],
+            filter: HistoryFilter::default(),
+            error: None,
+            loading: false,
+            page: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum HistoryMessage {
+    PathChanged(String),
+    Load,
+    Loaded(Result<Vec<HistoryEntry>, String>),
+    FilterChanged(HistoryFilter),
+    PageUp,
+    PageDown,
+    Undo(HistoryEntry),
+}
+
+impl HistoryView {
+    fn filtered(&self) -> Vec<&HistoryEntry> {
+        self.entries.iter().rev().filter(|e| self.filter.matches(e)).collect()
+    }
+
+    pub fn update(&mut self, message: AppMessage) -> Task<AppMessage> {
+        match message {
+            AppMessage::HistoryMessage(m) => match m {
+                HistoryMessage::PathChanged(path) => {
+                    self.path = path;
+                    Task::none()
+                }
+                HistoryMessage::Load => {
+                    self.loading = true;
+                    let path = PathBuf::from(&self.path);
+                    Task::perform(async move { history::load(&path) }, |r| {
+                        AppMessage::HistoryMessage(HistoryMessage::Loaded(r))
+                    })
+                }
+                HistoryMessage::Loaded(result) => {
+                    self.loading = false;
+                    match result {
+                        Ok(entries) => {
+                            self.entries = entries;
+                            self.error = None;
+                        }
+                        Err(e) => {
+                            self.entries.clear();
+                            self.error = Some(e);
+                        }
+                    }
+                    self.page = 0;
+                    Task::none()
+                }
+                HistoryMessage::FilterChanged(filter) => {
+                    self.filter = filter;
+                    self.page = 0;
+                    Task::none()
+                }
+                HistoryMessage::PageUp => {
+                    if (self.page + 1) * PAGE_SIZE < self.filtered().len() {
+                        self.page += 1;
+                    }
+                    Task::none()
+                }
+                HistoryMessage::PageDown => {
+                    self.page = self.page.saturating_sub(1);
+                    Task::none()
+                }
+                HistoryMessage::Undo(entry) => {
+                    let cached = cache::scan(&cache::cache_dirs(Path::new(PACMAN_CONF)));
+                    let installed = self
+                        .server
+                        .lock()
+                        .unwrap()
+                        .installed()
+                        .into_iter()
+                        .filter_map(|p| Some((p.get_property("Name".to_string())?, p.get_property("Version".to_string())?)))
+                        .collect();
+                    let transaction = entry.undo(&cached, &installed);
+                    if transaction.is_empty() {
+                        let _ = native_dialog::MessageDialog::new()
+                            .set_text(&transaction.notes.join("\n"))
+                            .set_title("Nothing to undo")
+                            .show_alert();
+                        return Task::none();
+                    }
+                    Task::done(AppMessage::TransactionMessage(TransactionMessage::Request(transaction)))
+                }
+            },
+            _ => Task::none(),
+        }
+    }
+
+    fn view_entry(entry: &HistoryEntry) -> iced::Element<'_, AppMessage> {
+        column![
+            row![
+                text(entry.timestamp.clone()),
+                text(entry.command.clone().unwrap_or("(no command recorded)".to_string())).font(iced::Font::MONOSPACE),
+                iced::widget::horizontal_space(),
+                button("Undo").on_press(AppMessage::HistoryMessage(HistoryMessage::Undo(entry.clone()))),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            column(entry.changes.iter().map(|c| text(c.describe()).size(12).into())),
+            column(entry.warnings.iter().map(|w| text(format!("warning: {}", w)).size(12).style(text::danger).into())),
+        ]
+        .spacing(2)
+        .into()
+    }
+
+    pub fn view(&self) -> Column<'_, AppMessage> {
+        let filtered = self.filtered();
+        let pages = filtered.len().div_ceil(PAGE_SIZE).max(1);
+        let entries = scrollable(
+            column(
+                filtered
+                    .iter()
+                    .skip(self.page * PAGE_SIZE)
+                    .take(PAGE_SIZE)
+                    .map(|e| Self::view_entry(e)),
+            )
+            .spacing(10),
+        )
+        .height(iced::Length::Fill);
+
+        let filter = |f: fn(&mut HistoryFilter, String)| {
+            let current = self.filter.clone();
+            move |value: String| {
+                let mut filter = current.clone();
+                f(&mut filter, value);
+                AppMessage::HistoryMessage(HistoryMessage::FilterChanged(filter))
+            }
+        };
+
+        column![
+            row![
+                iced::widget::text_input("pacman.log", &self.path)
+                    .on_input(|x| AppMessage::HistoryMessage(HistoryMessage::PathChanged(x)))
+                    .on_submit(AppMessage::HistoryMessage(HistoryMessage::Load)),
+                button("Load").on_press_maybe((!self.loading).then_some(AppMessage::HistoryMessage(HistoryMessage::Load))),
+            ]
+            .spacing(10),
+            row![
+                iced::widget::text_input("package", &self.filter.package).on_input(filter(|f, v| f.package = v)),
+                iced::widget::text_input("from (YYYY-MM-DD)", &self.filter.from).on_input(filter(|f, v| f.from = v)),
+                iced::widget::text_input("to (YYYY-MM-DD)", &self.filter.to).on_input(filter(|f, v| f.to = v)),
+            ]
+            .spacing(10),
+            text(match &self.error {
+                Some(e) => e.clone(),
+                None => format!("{} of {} transaction(s)", filtered.len(), self.entries.len()),
+            }),
+            entries,
+            row![
+                button("<").on_press(AppMessage::HistoryMessage(HistoryMessage::PageDown)),
+                text(format!("{}/{}", self.page + 1, pages)),
+                button(">").on_press(AppMessage::HistoryMessage(HistoryMessage::PageUp)),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+        ]
+        .spacing(10)
+    }
+}
@@ -0,0 +1,137 @@
+use iced::Task;
+use iced::widget::{Column, button, column, row, scrollable, text};
+use std::sync::{Arc, Mutex};
+
+use crate::AppMessage;
+use crate::logic::integrity::{self, IntegrityReport, IssueKind};
+use crate::logic::package::Package;
+use crate::ui::package_button::PackageCardMessage;
+
+//Runs `pacman -Qk`/`-Qkk` and lists missing, altered and modified backup files
+#[derive(Default, Debug, Clone)]
+pub struct IntegrityView {
+    //Package selected anywhere in the app, the target of "Check selected"
+    pub package: Option<Arc<Mutex<Package>>>,
+    pub thorough: bool,
+    pub loading: bool,
+    //What was checked and its result
+    pub report: Option<(String, Result<IntegrityReport, String>)>,
+}
+
+#[derive(Debug, Clone)]
+pub enum IntegrityMessage {
+    ThoroughToggled(bool),
+    CheckSelected,
+    CheckSystem,
+    Checked(String, Result<IntegrityReport, String>),
+}
+
+impl IntegrityView {
+    fn check(&mut self, package: Option<String>) -> Task<AppMessage> {
+        self.loading = true;
+        let thorough = self.thorough;
+        let label = package.clone().unwrap_or("the whole system".to_string());
+        Task::perform(async move { integrity::check(package, thorough) }, move |r| {
+            AppMessage::IntegrityMessage(IntegrityMessage::Checked(label.clone(), r))
+        })
+    }
+
+    pub fn update(&mut self, message: AppMessage) -> Task<AppMessage> {
+        match message {
+            AppMessage::PackageCardMessage(PackageCardMessage::Selected(p)) => {
+                self.package = Some(p);
+                Task::none()
+            }
+            AppMessage::IntegrityMessage(m) => match m {
+                IntegrityMessage::ThoroughToggled(b) => {
+                    self.thorough = b;
+                    Task::none()
+                }
+                IntegrityMessage::CheckSelected => {
+                    let name = self
+                        .package
+                        .as_ref()
+                        .and_then(|p| p.lock().unwrap().get_property("Name".to_string()));
+                    match name {
+                        Some(name) => self.check(Some(name)),
+                        None => Task::none(),
+                    }
+                }
+                IntegrityMessage::CheckSystem => self.check(None),
+                IntegrityMessage::Checked(label, report) => {
+                    self.loading = false;
+                    self.report = Some((label, report));
+                    Task::none()
+                }
+            },
+            _ => Task::none(),
+        }
+    }
+
+    pub fn view(&self) -> Column<'_, AppMessage> {
+        let selected_installed = self
+            .package
+            .as_ref()
+            .map(|p| p.lock().unwrap().is_installed())
+            .unwrap_or(false);
+
+        let controls = row![
+            button("Check selected package").on_press_maybe(
+                (!self.loading && selected_installed)
+                    .then_some(AppMessage::IntegrityMessage(IntegrityMessage::CheckSelected))
+            ),
+            button("Check whole system").on_press_maybe(
+                (!self.loading).then_some(AppMessage::IntegrityMessage(IntegrityMessage::CheckSystem))
+            ),
+            iced::widget::checkbox("Thorough (-Qkk)", self.thorough)
+                .on_toggle(|b| AppMessage::IntegrityMessage(IntegrityMessage::ThoroughToggled(b))),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+
+        let section = |title: &str, report: &IntegrityReport, kind: IssueKind| {
+            let issues = report.of_kind(kind);
+            column![
+                text(format!("{} ({})", title, issues.len())).size(18),
+                column(issues.into_iter().map(|i| {
+                    row![
+                        text(i.package.clone()).width(iced::Length::FillPortion(1)),
+                        text(i.path.clone()).width(iced::Length::FillPortion(3)),
+                        text(i.reason.clone()).width(iced::Length::FillPortion(2)),
+                    ]
+                    .spacing(10)
+                    .into()
+                }))
+                .spacing(2)
+            ]
+            .spacing(5)
+        };
+
+        let body: iced::Element<AppMessage> = match &self.report {
+            _ if self.loading => iced_aw::Spinner::new().circle_radius(20.0).into(),
+            None => text("Run a check to see results").into(),
+            Some((label, Err(e))) => text(format!("Checking {} failed:\n{}", label, e)).into(),
+            Some((label, Ok(report))) => column![
+                text(format!(
+                    "Checked {}: {} package(s), {} issue(s)",
+                    label,
+                    report.packages_checked,
+                    report.issues.len()
+                )),
+                scrollable(
+                    column![
+                        section("Missing files", report, IssueKind::Missing),
+                        section("Altered files", report, IssueKind::Altered),
+                        section("Backup files", report, IssueKind::Backup),
+                    ]
+                    .spacing(20)
+                )
+                .height(iced::Length::Fill)
+            ]
+            .spacing(10)
+            .into(),
+        };
+
+        column![controls, body].spacing(10)
+    }
+}
@@ -0,0 +1,192 @@
+use iced::Task;
+use iced::widget::{Column, button, column, row, scrollable, text};
+use std::sync::{Arc, Mutex};
+
+use crate::AppMessage;
+use crate::logic::keyring::{self, Key};
+use crate::logic::package::Package;
+use crate::ui::package_button::PackageCardMessage;
+use crate::ui::package_display::PackageViewMessage;
+use crate::ui::transaction::TransactionMessage;
+
+//Lists the pacman keyring, shows who signed the selected package and runs the pacman-key maintenance actions
+#[derive(Default, Debug, Clone)]
+pub struct KeyringView {
+    //Package selected anywhere in the app, its signature details are shown on top
+    pub package: Option<Arc<Mutex<Package>>>,
+    pub keys: Option<Result<Vec<Key>, String>>,
+    pub query: String,
+    //Key to fetch and sign, filled in with the key the last failed transaction was missing
+    pub import_key: String,
+    pub loading: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum KeyringMessage {
+    Load,
+    Loaded(Result<Vec<Key>, String>),
+    QueryChanged(String),
+    Refresh,
+    Populate,
+    Lsign(String),
+    ImportKeyChanged(String),
+    Import,
+    ActionFinished((bool, String)),
+}
+
+impl KeyringView {
+    fn run(&mut self, action: impl FnOnce() -> (bool, String) + Send + 'static) -> Task<AppMessage> {
+        self.loading = true;
+        Task::perform(async move { action() }, |result| {
+            AppMessage::KeyringMessage(KeyringMessage::ActionFinished(result))
+        })
+    }
+
+    pub fn update(&mut self, message: AppMessage) -> Task<AppMessage> {
+        match message {
+            AppMessage::PackageCardMessage(PackageCardMessage::Selected(p)) => {
+                self.package = Some(p);
+                Task::none()
+            }
+            AppMessage::TransactionMessage(TransactionMessage::Finished(stderror))
+            | AppMessage::PackageViewMessage(PackageViewMessage::FinishedSystemUpdate(stderror)) => {
+                if let Some(key) = keyring::missing_key(&stderror) {
+                    self.import_key = key;
+                }
+                Task::none()
+            }
+            AppMessage::KeyringMessage(m) => match m {
+                KeyringMessage::Load => {
+                    self.loading = true;
+                    Task::perform(async move { keyring::list_keys() }, |k| {
+                        AppMessage::KeyringMessage(KeyringMessage::Loaded(k))
+                    })
+                }
+                KeyringMessage::Loaded(keys) => {
+                    self.loading = false;
+                    self.keys = Some(keys);
+                    Task::none()
+                }
+                KeyringMessage::QueryChanged(query) => {
+                    self.query = query;
+                    Task::none()
+                }
+                KeyringMessage::Refresh => self.run(keyring::refresh),
+                KeyringMessage::Populate => self.run(keyring::populate),
+                KeyringMessage::Lsign(fingerprint) => self.run(move || keyring::lsign(&fingerprint)),
+                KeyringMessage::ImportKeyChanged(key) => {
+                    self.import_key = key;
+                    Task::none()
+                }
+                KeyringMessage::Import => {
+                    let key = self.import_key.trim().to_string();
+                    self.run(move || keyring::import(&key))
+                }
+                KeyringMessage::ActionFinished((success, stderror)) => {
+                    self.loading = false;
+                    if !success {
+                        let _ = native_dialog::MessageDialog::new()
+                            .set_text(&keyring::with_hint(stderror))
+                            .set_title("An error has ocurred :(")
+                            .show_alert();
+                    }
+                    self.update(AppMessage::KeyringMessage(KeyringMessage::Load))
+                }
+            },
+            _ => Task::none(),
+        }
+    }
+
+    fn view_key(&self, key: &Key) -> iced::Element<'_, AppMessage> {
+        let status = if key.revoked {
+            text("revoked").style(text::danger)
+        } else if key.expired {
+            text("expired").style(text::danger)
+        } else {
+            text(key.expires.clone().map(|e| format!("expires {}", e)).unwrap_or("does not expire".to_string()))
+        };
+        column![
+            row![
+                text(key.fingerprint.clone()).font(iced::Font::MONOSPACE).size(12),
+                text(format!("{} {}", key.algorithm, key.created)).size(12),
+                status.size(12),
+                iced::widget::horizontal_space(),
+                button("Sign locally").on_press_maybe(
+                    (!self.loading).then_some(AppMessage::KeyringMessage(KeyringMessage::Lsign(key.fingerprint.clone())))
+                ),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            column(key.uids.iter().map(|(validity, uid)| text(format!("[{}] {}", validity, uid)).size(12).into())),
+        ]
+        .spacing(2)
+        .into()
+    }
+
+    pub fn view(&self) -> Column<'_, AppMessage> {
+        let keys = match &self.keys {
+            Some(Ok(keys)) => keys.as_slice(),
+            _ => &[],
+        };
+
+        let signature = match &self.package {
+            Some(package) => {
+                let package = package.lock().unwrap();
+                let packager = package.get_property("Packager".to_string()).unwrap_or("Unknown Packager".to_string());
+                let signers = keyring::packager_keys(keys, &packager);
+                column![
+                    text(format!("{}:", package.get_property("Name".to_string()).unwrap_or_default())),
+                    text(format!("Packager: {}", packager)),
+                    text(format!(
+                        "Validated By: {}",
+                        package.get_property("Validated By".to_string()).unwrap_or("Unknown".to_string())
+                    )),
+                    text(if signers.is_empty() {
+                        "No key of the packager in the keyring, import the key it was signed with below".to_string()
+                    } else {
+                        format!("Packager key(s): {}", signers.iter().map(|k| k.fingerprint.clone()).collect::<Vec<_>>().join(", "))
+                    }),
+                ]
+                .spacing(2)
+            }
+            None => column![text("Select a package to see its signature")],
+        };
+
+        let import = row![
+            iced::widget::text_input("Key ID or fingerprint missing from the keyring", &self.import_key)
+                .on_input(|k| AppMessage::KeyringMessage(KeyringMessage::ImportKeyChanged(k))),
+            button("Import and sign").on_press_maybe(
+                (!self.loading && !self.import_key.trim().is_empty()).then_some(AppMessage::KeyringMessage(KeyringMessage::Import))
+            ),
+        ]
+        .spacing(10);
+
+        let listed = keys.iter().filter(|k| k.matches(&self.query)).collect::<Vec<_>>();
+        let header = match &self.keys {
+            Some(Err(e)) => text(e.clone()).style(text::danger),
+            None => text("Loading keys…"),
+            Some(Ok(_)) => text(format!("{} of {} key(s)", listed.len(), keys.len())),
+        };
+
+        column![
+            signature,
+            import,
+            row![
+                button("Refresh keys").on_press_maybe((!self.loading).then_some(AppMessage::KeyringMessage(KeyringMessage::Refresh))),
+                button("Populate keyring").on_press_maybe((!self.loading).then_some(AppMessage::KeyringMessage(KeyringMessage::Populate))),
+                if self.loading {
+                    iced::Element::from(iced_aw::Spinner::new())
+                } else {
+                    iced::Element::from(iced::widget::horizontal_space().width(0))
+                },
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            iced::widget::text_input("Filter by fingerprint, name or e-mail", &self.query)
+                .on_input(|q| AppMessage::KeyringMessage(KeyringMessage::QueryChanged(q))),
+            header,
+            scrollable(column(listed.into_iter().map(|k| self.view_key(k))).spacing(10)).height(iced::Length::Fill),
+        ]
+        .spacing(10)
+    }
+}
@@ -0,0 +1,150 @@
+use iced::Task;
+use iced::widget::{Column, button, column, row, text};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::AppMessage;
+use crate::logic::lock::{self, LockState, PROC_PATH};
+
+//How long to keep retrying before leaving it to the user
+const WAIT_SECONDS: u32 = 30;
+
+//Holds back an operation while the package database is locked, retrying every second
+#[derive(Debug, Clone)]
+pub struct LockView {
+    //The message that started the operation, sent again once the lock is gone
+    pub pending: Option<Box<AppMessage>>,
+    pub state: LockState,
+    pub countdown: u32,
+    pub loading: bool,
+}
+
+impl Default for LockView {
+    fn default() -> Self {
+        LockView {
+            pending: None,
+            state: LockState::Free,
+            countdown: 0,
+            loading: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum LockMessage {
+    Wait(Box<AppMessage>),
+    Tick,
+    Retry,
+    RemoveStale,
+    Removed(String),
+    Cancel,
+}
+
+//Operations that need the database call this first, it parks `message` while the lock is held
+pub fn wait_if_locked(message: AppMessage) -> Option<Task<AppMessage>> {
+    match lock::check(&lock::lock_file(), Path::new(PROC_PATH)) {
+        LockState::Free => None,
+        _ => Some(Task::done(AppMessage::LockMessage(LockMessage::Wait(Box::new(message))))),
+    }
+}
+
+fn tick() -> Task<AppMessage> {
+    Task::perform(
+        async move {
+            std::thread::sleep(Duration::from_secs(1));
+        },
+        |_| AppMessage::LockMessage(LockMessage::Tick),
+    )
+}
+
+impl LockView {
+    //Sends the parked message again when the lock is gone, otherwise counts down
+    fn recheck(&mut self) -> Task<AppMessage> {
+        self.state = lock::check(&lock::lock_file(), Path::new(PROC_PATH));
+        if self.state == LockState::Free {
+            self.countdown = 0;
+            return match self.pending.take() {
+                Some(message) => Task::done(*message),
+                None => Task::none(),
+            };
+        }
+        if self.countdown == 0 {
+            return Task::none();
+        }
+        self.countdown -= 1;
+        tick()
+    }
+
+    pub fn update(&mut self, message: AppMessage) -> Task<AppMessage> {
+        match message {
+            AppMessage::LockMessage(m) => match m {
+                LockMessage::Wait(message) => {
+                    let waiting = self.pending.is_some() && self.countdown > 0;
+                    self.pending = Some(message);
+                    self.countdown = WAIT_SECONDS;
+                    //A countdown already running keeps ticking for the new message
+                    if waiting { Task::none() } else { self.recheck() }
+                }
+                LockMessage::Tick => {
+                    if self.pending.is_none() {
+                        return Task::none();
+                    }
+                    self.recheck()
+                }
+                LockMessage::Retry => {
+                    self.countdown = WAIT_SECONDS;
+                    self.recheck()
+                }
+                LockMessage::RemoveStale => {
+                    self.loading = true;
+                    Task::perform(
+                        async move { lock::remove_stale(&lock::lock_file(), Path::new(PROC_PATH)) },
+                        |stderror| AppMessage::LockMessage(LockMessage::Removed(stderror)),
+                    )
+                }
+                LockMessage::Removed(stderror) => {
+                    self.loading = false;
+                    if !stderror.is_empty() {
+                        let _ = native_dialog::MessageDialog::new().set_text(&stderror).set_title("An error has ocurred :(").show_alert();
+                    }
+                    //A countdown still running picks the change up on its next tick
+                    if self.countdown > 0 { Task::none() } else { self.recheck() }
+                }
+                LockMessage::Cancel => {
+                    self.pending = None;
+                    self.countdown = 0;
+                    Task::none()
+                }
+            },
+            _ => Task::none(),
+        }
+    }
+
+    pub fn view(&self) -> Column<'_, AppMessage> {
+        let status = if self.countdown > 0 {
+            text(format!("Retrying for another {} second(s)…", self.countdown))
+        } else {
+            text("Stopped waiting, retry once the other operation has finished")
+        };
+
+        column![
+            text("Waiting for the package database").size(20),
+            text(self.state.describe()),
+            status,
+            row![
+                button("Retry").on_press_maybe(
+                    (self.countdown == 0 && !self.loading).then_some(AppMessage::LockMessage(LockMessage::Retry))
+                ),
+                //Only offered when no package manager is running, removing a live lock corrupts the database
+                button("Remove stale lock").style(button::danger).on_press_maybe(
+                    (self.state == LockState::Stale && !self.loading)
+                        .then_some(AppMessage::LockMessage(LockMessage::RemoveStale))
+                ),
+                button("Cancel").on_press(AppMessage::LockMessage(LockMessage::Cancel)),
+            ]
+            .spacing(10),
+        ]
+        .spacing(20)
+        .width(iced::Length::Fill)
+    }
+}
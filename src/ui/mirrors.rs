@@ -0,0 +1,217 @@
+use iced::Task;
+use iced::widget::{Column, button, column, row, scrollable, text};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::AppMessage;
+use crate::logic::files::format_size;
+use crate::logic::mirrors::{self, MIRRORLIST, Mirror, Mirrorlist, ProbeResult, Prober, RankBy};
+
+const ALL_COUNTRIES: &str = "All countries";
+
+//Lists the mirrorlist servers by country, reorders and ranks them and writes the result back as root
+#[derive(Debug, Clone)]
+pub struct MirrorsView {
+    pub path: String,
+    pub list: Mirrorlist,
+    //Rendered contents as last loaded, to tell whether there is anything to save
+    pub original: String,
+    pub country: String,
+    pub rank_by: RankBy,
+    //Last probe of every mirror, by URL
+    pub results: HashMap<String, Result<ProbeResult, String>>,
+    pub prober: Prober,
+    pub error: Option<String>,
+    pub loading: bool,
+}
+
+impl Default for MirrorsView {
+    fn default() -> Self {
+        MirrorsView {
+            path: MIRRORLIST.to_string(),
+            list: Mirrorlist::default(),
+            original: String::new(),
+            country: ALL_COUNTRIES.to_string(),
+            rank_by: RankBy::default(),
+            results: HashMap::new(),
+            prober: Prober::default(),
+            error: None,
+            loading: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum MirrorsMessage {
+    Load,
+    CountrySelected(String),
+    Toggled(usize, bool),
+    Move(usize, bool),
+    RankByChanged(RankBy),
+    Rank,
+    Ranked(Vec<(Mirror, Result<ProbeResult, String>)>),
+    Save,
+    Saved(String),
+}
+
+impl MirrorsView {
+    //Indexes into the list of the mirrors shown for the selected country
+    fn visible(&self) -> Vec<usize> {
+        (0..self.list.mirrors.len())
+            .filter(|i| self.country == ALL_COUNTRIES || self.list.mirrors[*i].country == self.country)
+            .collect()
+    }
+
+    pub fn update(&mut self, message: AppMessage) -> Task<AppMessage> {
+        match message {
+            AppMessage::MirrorsMessage(m) => match m {
+                MirrorsMessage::Load => {
+                    match Mirrorlist::load(&PathBuf::from(&self.path)) {
+                        Ok(list) => {
+                            self.original = list.render();
+                            self.list = list;
+                            self.error = None;
+                        }
+                        Err(e) => self.error = Some(e),
+                    }
+                    Task::none()
+                }
+                MirrorsMessage::CountrySelected(country) => {
+                    self.country = country;
+                    Task::none()
+                }
+                MirrorsMessage::Toggled(index, enabled) => {
+                    if let Some(mirror) = self.list.mirrors.get_mut(index) {
+                        mirror.enabled = enabled;
+                    }
+                    Task::none()
+                }
+                MirrorsMessage::Move(index, up) => {
+                    self.list.move_mirror(index, up);
+                    Task::none()
+                }
+                MirrorsMessage::RankByChanged(by) => {
+                    self.rank_by = by;
+                    Task::none()
+                }
+                MirrorsMessage::Rank => {
+                    let mirrors = self.visible().into_iter().map(|i| self.list.mirrors[i].clone()).collect::<Vec<_>>();
+                    let prober = self.prober.clone();
+                    let by = self.rank_by;
+                    self.loading = true;
+                    Task::perform(
+                        async move { mirrors::rank(&mirrors, prober.0.as_ref(), std::env::consts::ARCH, by) },
+                        |r| AppMessage::MirrorsMessage(MirrorsMessage::Ranked(r)),
+                    )
+                }
+                MirrorsMessage::Ranked(ranked) => {
+                    self.loading = false;
+                    //The ranked mirrors move to the top in their new order, the rest keep theirs.
+                    //They are matched by URL so edits made while probing, like toggling one, are kept
+                    let urls = ranked.iter().map(|(m, _)| m.url.clone()).collect::<Vec<_>>();
+                    let (mut mirrors, rest): (Vec<_>, Vec<_>) =
+                        self.list.mirrors.drain(..).partition(|m| urls.contains(&m.url));
+                    mirrors.sort_by_key(|m| urls.iter().position(|u| *u == m.url));
+                    mirrors.extend(rest);
+                    self.list.mirrors = mirrors;
+                    for (mirror, result) in ranked {
+                        self.results.insert(mirror.url, result);
+                    }
+                    Task::none()
+                }
+                MirrorsMessage::Save => {
+                    let list = self.list.clone();
+                    let path = PathBuf::from(&self.path);
+                    self.loading = true;
+                    Task::perform(async move { list.save(&path) }, |stderror| {
+                        AppMessage::MirrorsMessage(MirrorsMessage::Saved(stderror))
+                    })
+                }
+                MirrorsMessage::Saved(stderror) => {
+                    self.loading = false;
+                    if !stderror.is_empty() {
+                        let _ = native_dialog::MessageDialog::new().set_text(&stderror).set_title("An error has ocurred :(").show_alert();
+                        return Task::none();
+                    }
+                    self.update(AppMessage::MirrorsMessage(MirrorsMessage::Load))
+                }
+            },
+            _ => Task::none(),
+        }
+    }
+
+    pub fn view(&self) -> Column<'_, AppMessage> {
+        let mut countries = vec![ALL_COUNTRIES.to_string()];
+        for mirror in self.list.mirrors.iter() {
+            if !mirror.country.is_empty() && !countries.contains(&mirror.country) {
+                countries.push(mirror.country.clone());
+            }
+        }
+
+        let mirrors = column(self.visible().into_iter().map(|index| {
+            let mirror = &self.list.mirrors[index];
+            let result = match self.results.get(&mirror.url) {
+                Some(Ok(r)) => text(format!("{} ms, {}/s", r.latency.as_millis(), format_size(r.throughput as u64))),
+                Some(Err(_)) => text("unreachable").style(text::danger),
+                None => text(""),
+            };
+            row![
+                iced::widget::checkbox("", mirror.enabled)
+                    .on_toggle(move |b| AppMessage::MirrorsMessage(MirrorsMessage::Toggled(index, b))),
+                text(mirror.url.clone()).size(12),
+                iced::widget::horizontal_space(),
+                text(mirror.country.clone()).size(12),
+                result.size(12),
+                button("↑").on_press(AppMessage::MirrorsMessage(MirrorsMessage::Move(index, true))),
+                button("↓").on_press(AppMessage::MirrorsMessage(MirrorsMessage::Move(index, false))),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center)
+            .into()
+        }))
+        .spacing(5);
+
+        let rank_button = |label: &'static str, by: RankBy| {
+            button(label)
+                .style(if self.rank_by == by { button::primary } else { button::secondary })
+                .on_press(AppMessage::MirrorsMessage(MirrorsMessage::RankByChanged(by)))
+        };
+
+        let enabled = self.list.mirrors.iter().filter(|m| m.enabled).count();
+        let changed = self.list.render() != self.original;
+
+        column![
+            row![
+                text(self.path.clone()),
+                iced::widget::horizontal_space(),
+                button("Reload").on_press_maybe((!self.loading).then_some(AppMessage::MirrorsMessage(MirrorsMessage::Load))),
+                button("Save").on_press_maybe(
+                    (!self.loading && changed && enabled > 0).then_some(AppMessage::MirrorsMessage(MirrorsMessage::Save))
+                ),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            text(match &self.error {
+                Some(e) => e.clone(),
+                None => format!("{} of {} mirror(s) enabled{}", enabled, self.list.mirrors.len(), if changed { ", unsaved changes" } else { "" }),
+            }),
+            row![
+                iced::widget::pick_list(countries, Some(self.country.clone()), |c| {
+                    AppMessage::MirrorsMessage(MirrorsMessage::CountrySelected(c))
+                }),
+                rank_button("By latency", RankBy::Latency),
+                rank_button("By throughput", RankBy::Throughput),
+                button("Rank shown mirrors").on_press_maybe((!self.loading).then_some(AppMessage::MirrorsMessage(MirrorsMessage::Rank))),
+                if self.loading {
+                    iced::Element::from(iced_aw::Spinner::new())
+                } else {
+                    iced::Element::from(iced::widget::horizontal_space().width(0))
+                },
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            scrollable(mirrors).height(iced::Length::Fill),
+        ]
+        .spacing(10)
+    }
+}
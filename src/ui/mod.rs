@@ -1,3 +1,17 @@
+pub mod cache;
+pub mod config_files;
+pub mod groups;
+pub mod history;
+pub mod integrity;
+pub mod keyring;
+pub mod lock;
+pub mod mirrors;
 pub mod search;
+pub mod snapshots;
 pub mod package_button;
 pub mod package_display;
+pub mod pacman_conf;
+pub mod profile;
+pub mod reconcile;
+pub mod repositories;
+pub mod transaction;
@@ -43,7 +43,25 @@ impl PackageButton {
         .align_x(iced::Alignment::End)
         .style(if installed {style::badge::success} else {style::badge::warning});
 
-        return button(row![name, iced::widget::horizontal_space().width(iced::Length::Fill), icon].spacing(10).padding(5))
+        //Foreign packages have no repository, they get their own badge so they stand out
+        let (repository, foreign, aur, held) = {
+            let package = self.package.lock().unwrap();
+            (package.get_property("Repository".to_string()), package.is_foreign(), package.is_aur(), package.is_held())
+        };
+        let repo_badge = match repository {
+            _ if aur => iced::Element::from(iced_aw::badge("AUR").style(style::badge::primary)),
+            _ if foreign => iced::Element::from(iced_aw::badge("Foreign").style(style::badge::danger)),
+            Some(repo) => iced::Element::from(iced_aw::badge(iced::widget::text(repo)).style(style::badge::info)),
+            None => iced::Element::from(iced::widget::horizontal_space().width(0)),
+        };
+
+        let held_badge = if held {
+            iced::Element::from(iced_aw::badge("Held").style(style::badge::secondary))
+        } else {
+            iced::Element::from(iced::widget::horizontal_space().width(0))
+        };
+
+        return button(row![name, iced::widget::horizontal_space().width(iced::Length::Fill), held_badge, repo_badge, icon].spacing(10).padding(5))
             .width(iced::Length::Fill)
             .on_press(AppMessage::PackageCardMessage(
                 PackageCardMessage::Selected(self.package.clone()),
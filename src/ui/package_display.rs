@@ -3,7 +3,12 @@ use iced::{
     Task,
     widget::{button, column, row, text},
 };
-use std::sync::{Arc, Mutex};
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    path::Path,
+    sync::{Arc, Mutex},
+};
 
 use crate::{
     AppMessage,
@@ -11,6 +16,16 @@ use crate::{
 };
 
 use super::package_button::PackageCardMessage;
+use super::lock;
+use super::transaction::TransactionMessage;
+use crate::logic::aur::{self, BuildOutcome};
+use crate::logic::cache::{self, CachedPackage};
+use crate::logic::files::{self, FileNode, format_size};
+use crate::logic::history::{self, PackageChange};
+use crate::logic::hold::{self, HoldScope};
+use crate::logic::keyring;
+use crate::logic::pacman_conf::PACMAN_CONF;
+use crate::logic::transaction::Transaction;
 
 #[derive(Debug, Clone)]
 pub enum PackageViewMessage {
@@ -18,77 +33,230 @@ pub enum PackageViewMessage {
     Uninstall(Arc<Mutex<Package>>),
     Update(Arc<Mutex<Package>>),
     SystemUpdate,
-    Finished(String, Arc<Mutex<Package>>),
+    InstallFromFile,
+    ReviewPkgbuild,
+    //Reviewed commit of the clone and its PKGBUILD
+    PkgbuildLoaded(Result<(String, String), String>),
+    BuildAur,
+    AurBuilt(String, Result<BuildOutcome, String>),
     FinishedSystemUpdate(String),
+    TabSelected(DisplayTab),
+    FilesLoaded(Result<FileNode, String>),
+    HistoryLoaded(Result<Vec<(String, PackageChange)>, String>),
+    ToggleDirectory(String),
+    Downgrade,
+    DowngradeTo(CachedPackage),
+    IgnoreAfterDowngradeToggled(bool),
+    ToggleHold,
+    HoldSystemWideToggled(bool),
+    HoldChanged(String, bool),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisplayTab {
+    #[default]
+    Info,
+    Files,
+    History,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct PackageDisplay {
     pub server: Arc<Mutex<Server>>,
     pub package: Option<Arc<Mutex<Package>>>,
     pub loading: bool,
+    //PKGBUILD of the selected AUR package, shown for review before building
+    pub pkgbuild: Option<String>,
+    //Commit of the clone whose PKGBUILD was reviewed, building is only offered once it is set
+    pub reviewed: Option<String>,
+    pub tab: DisplayTab,
+    //File tree of the selected package, loaded when the files tab is shown
+    pub files: Option<Result<FileNode, String>>,
+    pub expanded: HashSet<String>,
+    //Changes to the selected package recorded in pacman.log, oldest first, loaded when the history tab is shown
+    pub history: Option<Result<Vec<(String, PackageChange)>, String>>,
+    //Cached versions of the selected package compared with the installed one, shown when downgrading
+    pub downgrade: Option<Vec<(CachedPackage, Ordering)>>,
+    pub ignore_after_downgrade: bool,
+    //New holds go to IgnorePkg instead of the app hold list
+    pub hold_system_wide: bool,
+    //List holding the selected package, read when it is selected and after every hold change
+    pub held_by: Option<HoldScope>,
 }
 
 impl PackageDisplay {
-    fn handle_operation(&self, operation: PackageViewMessage) -> String {
-        let package_name = self
-            .clone()
-            .package
-            .unwrap()
-            .lock()
-            .unwrap()
-            .get_property("Name".to_string())
-            .unwrap_or_default();
-        if package_name.len() == 0 {
-            return "".to_string();
+    //Loads whatever the current tab needs for the selected package
+    fn load_tab(&mut self) -> Task<AppMessage> {
+        let Some(package) = self.package.clone() else {
+            return Task::none();
+        };
+        match self.tab {
+            DisplayTab::Files if self.files.is_none() => {
+                let (name, installed) = {
+                    let p = package.lock().unwrap();
+                    (p.get_property("Name".to_string()).unwrap_or_default(), p.is_installed())
+                };
+                Task::perform(
+                    async move {
+                        if installed {
+                            files::installed_files(name)
+                        } else {
+                            files::remote_files(name)
+                        }
+                    },
+                    |f| AppMessage::PackageViewMessage(PackageViewMessage::FilesLoaded(f)),
+                )
+            }
+            DisplayTab::History if self.history.is_none() => {
+                let name = package.lock().unwrap().get_property("Name".to_string()).unwrap_or_default();
+                Task::perform(
+                    async move {
+                        let entries = history::load(&history::log_path(Path::new(PACMAN_CONF)))?;
+                        Ok(entries
+                            .iter()
+                            .filter_map(|e| Some((e.timestamp.clone(), e.change_for(&name)?.clone())))
+                            .collect())
+                    },
+                    |h| AppMessage::PackageViewMessage(PackageViewMessage::HistoryLoaded(h)),
+                )
+            }
+            _ => Task::none(),
+        }
+    }
+
+    fn view_history(&self) -> iced::Element<'_, AppMessage> {
+        match &self.history {
+            _ if self.package.is_none() => text("No package selected").into(),
+            None => iced_aw::Spinner::new().circle_radius(20.0).into(),
+            Some(Err(e)) => text(format!("Could not read the history:\n{}", e)).into(),
+            Some(Ok(changes)) if changes.is_empty() => text("pacman.log has no record of this package").into(),
+            Some(Ok(changes)) => iced::widget::scrollable(
+                column(changes.iter().rev().map(|(timestamp, change)| {
+                    row![text(timestamp.clone()), text(change.describe())].spacing(10).into()
+                }))
+                .spacing(5),
+            )
+            .height(iced::Length::Fill)
+            .into(),
         }
-        return match operation {
-            PackageViewMessage::Install(p) | PackageViewMessage::Update(p) => {
-                Package::install_or_update(package_name)
+    }
+
+    //Flattens the expanded part of the tree into indented rows
+    fn view_tree<'a>(&self, node: &'a FileNode, depth: u16, rows: &mut Vec<iced::Element<'a, AppMessage>>) {
+        for child in node.children.values() {
+            let size = text(child.size.map(format_size).unwrap_or_default());
+            let indent = iced::widget::horizontal_space().width(depth * 16);
+            if child.is_dir() {
+                let open = self.expanded.contains(&child.path);
+                rows.push(
+                    row![
+                        indent,
+                        button(text(format!("{} {}", if open { "▾" } else { "▸" }, child.name)))
+                            .style(button::text)
+                            .padding(0)
+                            .on_press(AppMessage::PackageViewMessage(PackageViewMessage::ToggleDirectory(child.path.clone()))),
+                        text(format!("{} file(s)", child.file_count())),
+                        iced::widget::horizontal_space(),
+                        size
+                    ]
+                    .spacing(10)
+                    .into(),
+                );
+                if open {
+                    self.view_tree(child, depth + 1, rows);
+                }
+            } else {
+                rows.push(row![indent, text(child.name.clone()), iced::widget::horizontal_space(), size].spacing(10).into());
             }
-            PackageViewMessage::Uninstall(p) => Package::uninstall(package_name),
-            _ => {
-                unreachable!()
+        }
+    }
+
+    fn view_files(&self) -> iced::Element<'_, AppMessage> {
+        match &self.files {
+            _ if self.package.is_none() => text("No package selected").into(),
+            None => iced_aw::Spinner::new().circle_radius(20.0).into(),
+            Some(Err(e)) => text(format!("Could not list files:\n{}", e)).into(),
+            Some(Ok(tree)) => {
+                let mut rows = vec![];
+                self.view_tree(tree, 0, &mut rows);
+                column![
+                    text(format!(
+                        "{} file(s), {}",
+                        tree.file_count(),
+                        tree.size.map(format_size).unwrap_or("unknown size".to_string())
+                    )),
+                    iced::widget::scrollable(column(rows).spacing(2).padding(5)).height(iced::Length::Fill)
+                ]
+                .spacing(10)
+                .into()
             }
-        };
+        }
+    }
+
+    fn package_base(package: &Arc<Mutex<Package>>) -> String {
+        let package = package.lock().unwrap();
+        package
+            .get_property("Package Base".to_string())
+            .or(package.get_property("Name".to_string()))
+            .unwrap_or_default()
+    }
+
+    fn load_hold_scope(&mut self) {
+        self.held_by = self.package.as_ref().and_then(|p| {
+            let name = p.lock().unwrap().get_property("Name".to_string()).unwrap_or_default();
+            hold::scope_of(Path::new(PACMAN_CONF), &hold::hold_file(), &name)
+        });
     }
 
     pub fn update(&mut self, message: AppMessage) -> Task<AppMessage> {
         match message {
             AppMessage::PackageCardMessage(PackageCardMessage::Selected(p)) => {
                 self.package = Some(p);
+                self.pkgbuild = None;
+                self.reviewed = None;
+                self.files = None;
+                self.history = None;
+                self.downgrade = None;
+                self.expanded.clear();
                 self.package.as_mut().unwrap().lock().unwrap().sync_all();
-                iced::Task::none()
+                self.load_hold_scope();
+                self.load_tab()
             }
-            AppMessage::PackageViewMessage(m) => match m {
-                PackageViewMessage::Update(_)
-                | PackageViewMessage::Install(_)
-                | PackageViewMessage::Uninstall(_) => {
-                    self.loading = true;
-                    let this = self.clone();
-                    let package = this.package.clone().unwrap();
-                    iced::Task::perform(
-                        async move { this.clone().handle_operation(m.clone()) },
-                        move |installed| {
-                            AppMessage::PackageViewMessage(PackageViewMessage::Finished(
-                                installed,
-                                package.clone(),
-                            ))
-                        },
-                    )
-                }
-                PackageViewMessage::Finished(stderror, package) => {
-                	if stderror.len() != 0 {
-                 		let _ = native_dialog::MessageDialog::new().set_text(&stderror).set_title("An error has ocurred :(").show_alert();
-                 	} else {
-                  		let _ = native_dialog::MessageDialog::new().set_title("Operation finished succesfully").set_text("No errors were reported").show_alert();
-                  	}
+            //The selected package may have been installed, updated or removed
+            AppMessage::TransactionMessage(TransactionMessage::Finished(_)) => {
+                if let Some(package) = self.package.clone() {
                     package.lock().unwrap().sync_installed();
                     package.lock().unwrap().sync_all();
-                    self.loading = false;
-                    Task::none()
+                }
+                self.load_hold_scope();
+                Task::none()
+            }
+            AppMessage::PackageViewMessage(m) => match m {
+                PackageViewMessage::Install(package) | PackageViewMessage::Update(package) => {
+                    //Installing through "repo/name" makes sure the variant the user picked is the one installed
+                    let (name, qualified_name, installed) = {
+                        let p = package.lock().unwrap();
+                        (p.get_property("Name".to_string()).unwrap_or_default(), p.get_qualified_name(), p.is_installed())
+                    };
+                    let verb = if installed { "Update" } else { "Install" };
+                    Task::done(AppMessage::TransactionMessage(TransactionMessage::Request(Transaction {
+                        description: format!("{} {}", verb, name),
+                        install: vec![qualified_name],
+                        ..Default::default()
+                    })))
+                }
+                PackageViewMessage::Uninstall(package) => {
+                    let name = package.lock().unwrap().get_property("Name".to_string()).unwrap_or_default();
+                    Task::done(AppMessage::TransactionMessage(TransactionMessage::Request(Transaction {
+                        description: format!("Uninstall {}", name),
+                        remove: vec![name],
+                        ..Default::default()
+                    })))
                 }
                 PackageViewMessage::SystemUpdate => {
+                    if let Some(wait) = lock::wait_if_locked(AppMessage::PackageViewMessage(PackageViewMessage::SystemUpdate)) {
+                        return wait;
+                    }
                     self.loading = true;
                     let this = self.clone();
 
@@ -101,9 +269,222 @@ impl PackageDisplay {
                         },
                     );
                 }
+                PackageViewMessage::InstallFromFile => {
+                    let paths = native_dialog::FileDialog::new()
+                        .set_title("Install from file")
+                        .add_filter("Pacman package", &["zst", "xz", "gz", "bz2"])
+                        .show_open_multiple_file()
+                        .unwrap_or_default();
+                    if paths.is_empty() {
+                        return Task::none();
+                    }
+
+                    let mut transaction = Transaction {
+                        description: format!("Install {} local package(s)", paths.len()),
+                        ..Default::default()
+                    };
+                    for path in paths {
+                        let path = path.to_string_lossy().to_string();
+                        match Package::from_archive(path.clone()) {
+                            Ok(p) => {
+                                let deps = p.get_list_property("Depends On".to_string());
+                                transaction.notes.push(format!(
+                                    "{} {} ({}) depends on: {}",
+                                    p.get_property("Name".to_string()).unwrap_or_default(),
+                                    p.get_property("Version".to_string()).unwrap_or_default(),
+                                    path,
+                                    if deps.is_empty() { "nothing".to_string() } else { deps.join(", ") }
+                                ));
+                                transaction.install_files.push(path);
+                            }
+                            Err(e) => {
+                                let _ = native_dialog::MessageDialog::new().set_text(&e).set_title("An error has ocurred :(").show_alert();
+                                return Task::none();
+                            }
+                        }
+                    }
+                    Task::done(AppMessage::TransactionMessage(TransactionMessage::Request(transaction)))
+                }
+                PackageViewMessage::ReviewPkgbuild => {
+                    if self.pkgbuild.is_some() {
+                        self.pkgbuild = None;
+                        return Task::none();
+                    }
+                    let Some(package) = self.package.clone() else {
+                        return Task::none();
+                    };
+                    let base = Self::package_base(&package);
+                    let backend = self.server.lock().unwrap().aur.clone();
+                    self.loading = true;
+                    Task::perform(async move { aur::checkout(backend.0.as_ref(), &base) }, |r| {
+                        AppMessage::PackageViewMessage(PackageViewMessage::PkgbuildLoaded(r))
+                    })
+                }
+                PackageViewMessage::PkgbuildLoaded(result) => {
+                    self.loading = false;
+                    match result {
+                        Ok((commit, pkgbuild)) => {
+                            self.reviewed = Some(commit);
+                            self.pkgbuild = Some(pkgbuild);
+                        }
+                        Err(e) => {
+                            let _ = native_dialog::MessageDialog::new().set_text(&e).set_title("An error has ocurred :(").show_alert();
+                        }
+                    }
+                    Task::none()
+                }
+                PackageViewMessage::BuildAur => {
+                    let (Some(package), Some(commit)) = (self.package.clone(), self.reviewed.clone()) else {
+                        return Task::none();
+                    };
+                    let name = package.lock().unwrap().get_property("Name".to_string()).unwrap_or_default();
+                    let base = Self::package_base(&package);
+                    self.loading = true;
+                    Task::perform(async move { aur::build(&base, &commit) }, move |r| {
+                        AppMessage::PackageViewMessage(PackageViewMessage::AurBuilt(name.clone(), r))
+                    })
+                }
+                PackageViewMessage::AurBuilt(name, result) => {
+                    self.loading = false;
+                    match result {
+                        Ok(BuildOutcome::MissingDependencies { repo, aur }) => {
+                            let mut notes = vec![format!("Build {} again once they are installed", name)];
+                            if !aur.is_empty() {
+                                notes.push(format!("Build these from the AUR first: {}", aur.join(" ")));
+                            }
+                            if repo.is_empty() {
+                                let _ = native_dialog::MessageDialog::new()
+                                    .set_text(&notes.join("\n"))
+                                    .set_title("Missing AUR dependencies")
+                                    .show_alert();
+                                return Task::none();
+                            }
+                            Task::done(AppMessage::TransactionMessage(TransactionMessage::Request(Transaction {
+                                description: format!("Install build dependencies of {}", name),
+                                notes,
+                                install: repo,
+                                ..Default::default()
+                            })))
+                        }
+                        Ok(BuildOutcome::Built(archives)) => Task::done(AppMessage::TransactionMessage(
+                            TransactionMessage::Request(Transaction {
+                                description: format!("Install {} built from the AUR", name),
+                                notes: archives.clone(),
+                                install_files: archives,
+                                ..Default::default()
+                            }),
+                        )),
+                        Err(e) => {
+                            let _ = native_dialog::MessageDialog::new().set_text(&e).set_title("An error has ocurred :(").show_alert();
+                            Task::none()
+                        }
+                    }
+                }
+                PackageViewMessage::TabSelected(tab) => {
+                    self.tab = tab;
+                    self.load_tab()
+                }
+                PackageViewMessage::FilesLoaded(files) => {
+                    self.files = Some(files);
+                    Task::none()
+                }
+                PackageViewMessage::HistoryLoaded(history) => {
+                    self.history = Some(history);
+                    Task::none()
+                }
+                PackageViewMessage::ToggleDirectory(path) => {
+                    if !self.expanded.remove(&path) {
+                        self.expanded.insert(path);
+                    }
+                    Task::none()
+                }
+                PackageViewMessage::Downgrade => {
+                    if self.downgrade.is_some() {
+                        self.downgrade = None;
+                        return Task::none();
+                    }
+                    let Some(package) = self.package.clone() else {
+                        return Task::none();
+                    };
+                    let (name, version) = {
+                        let p = package.lock().unwrap();
+                        (
+                            p.get_property("Name".to_string()).unwrap_or_default(),
+                            p.get_property("Version".to_string()).unwrap_or_default(),
+                        )
+                    };
+                    let dirs = cache::cache_dirs(Path::new(PACMAN_CONF));
+                    self.downgrade = Some(cache::cached_versions(&dirs, &name, &version));
+                    Task::none()
+                }
+                PackageViewMessage::DowngradeTo(cached) => {
+                    let ignore = if self.ignore_after_downgrade { vec![cached.name.clone()] } else { vec![] };
+                    let mut notes = vec![cached.path.to_string_lossy().to_string()];
+                    if !ignore.is_empty() {
+                        notes.push(format!("{} will be added to IgnorePkg in {}", cached.name, PACMAN_CONF));
+                    }
+                    self.downgrade = None;
+                    Task::done(AppMessage::TransactionMessage(TransactionMessage::Request(Transaction {
+                        description: format!("Downgrade {} to {}", cached.name, cached.version),
+                        notes,
+                        install_files: vec![cached.path.to_string_lossy().to_string()],
+                        ignore,
+                        ..Default::default()
+                    })))
+                }
+                PackageViewMessage::IgnoreAfterDowngradeToggled(b) => {
+                    self.ignore_after_downgrade = b;
+                    Task::none()
+                }
+                PackageViewMessage::ToggleHold => {
+                    let Some(package) = self.package.clone() else {
+                        return Task::none();
+                    };
+                    let (name, held) = {
+                        let p = package.lock().unwrap();
+                        (p.get_property("Name".to_string()).unwrap_or_default(), p.is_held())
+                    };
+                    //Releasing goes to whichever list holds the package, not the one picked for new holds
+                    let scope = match self.held_by {
+                        Some(scope) if held => scope,
+                        _ if self.hold_system_wide => HoldScope::IgnorePkg,
+                        _ => HoldScope::App,
+                    };
+                    self.loading = true;
+                    Task::perform(
+                        async move { hold::set_held(Path::new(PACMAN_CONF), &hold::hold_file(), &name, !held, scope) },
+                        move |stderror| AppMessage::PackageViewMessage(PackageViewMessage::HoldChanged(stderror, !held)),
+                    )
+                }
+                PackageViewMessage::HoldSystemWideToggled(b) => {
+                    self.hold_system_wide = b;
+                    Task::none()
+                }
+                PackageViewMessage::HoldChanged(stderror, held) => {
+                    self.loading = false;
+                    if !stderror.is_empty() {
+                        let _ = native_dialog::MessageDialog::new().set_text(&stderror).set_title("An error has ocurred :(").show_alert();
+                        return Task::none();
+                    }
+                    let Some(package) = self.package.clone() else {
+                        return Task::none();
+                    };
+                    let name = package.lock().unwrap().get_property("Name".to_string()).unwrap_or_default();
+                    for p in self.server.lock().unwrap().get_variants(name) {
+                        let mut p = p.lock().unwrap();
+                        if held {
+                            p.set_property("Held".to_string(), "True".to_string());
+                            p.remove_property("Upgradable".to_string());
+                        } else {
+                            p.remove_property("Held".to_string());
+                        }
+                    }
+                    self.load_hold_scope();
+                    Task::none()
+                }
                 PackageViewMessage::FinishedSystemUpdate(stderror) => {
                		if stderror.len() != 0 {
-                		let _ = native_dialog::MessageDialog::new().set_text(&stderror).set_title("An error has ocurred :(").show_alert();
+                		let _ = native_dialog::MessageDialog::new().set_text(&keyring::with_hint(stderror)).set_title("An error has ocurred :(").show_alert();
                 	} else {
                  		let _ = native_dialog::MessageDialog::new().set_title("Update finished succesfully").set_text("No errors were reported").show_alert();
                  	}
@@ -128,9 +509,14 @@ impl PackageDisplay {
             .unwrap_or_default()
             == "True".to_string();
 
+        //AUR packages are built locally instead of being fetched by pacman
+        let is_aur = package_lock.is_aur();
+
         let install_button = button(if installed { "Uninstall" } else { "Install" })
             .on_press_maybe(if self.loading == false && self.package.is_some() {
-                if installed {
+                if is_aur && !installed {
+                    self.reviewed.is_some().then_some(AppMessage::PackageViewMessage(PackageViewMessage::BuildAur))
+                } else if installed {
                     Some(AppMessage::PackageViewMessage(
                         PackageViewMessage::Uninstall(self.package.clone().unwrap_or_default()),
                     ))
@@ -145,22 +531,135 @@ impl PackageDisplay {
 
         let update_button =
             button("Update").on_press_maybe(if self.loading == false && self.package.is_some() {
-                Some(AppMessage::PackageViewMessage(PackageViewMessage::Update(
-                    self.package.clone().unwrap_or_default(),
-                )))
+                if is_aur {
+                    self.reviewed.is_some().then_some(AppMessage::PackageViewMessage(PackageViewMessage::BuildAur))
+                } else {
+                    Some(AppMessage::PackageViewMessage(PackageViewMessage::Update(
+                        self.package.clone().unwrap_or_default(),
+                    )))
+                }
             } else {
                 None
             });
 
+        let pkgbuild_button = if is_aur {
+            iced::Element::from(
+                button(if self.pkgbuild.is_some() { "Hide PKGBUILD" } else { "Review PKGBUILD" }).on_press_maybe(
+                    (!self.loading).then_some(AppMessage::PackageViewMessage(PackageViewMessage::ReviewPkgbuild)),
+                ),
+            )
+        } else {
+            iced::Element::from(iced::widget::horizontal_space().width(0))
+        };
+
+        let pkgbuild = match &self.pkgbuild {
+            Some(pkgbuild) => iced::Element::from(
+                iced::widget::scrollable(text(pkgbuild.clone()).font(iced::Font::MONOSPACE))
+                    .height(iced::Length::Fill)
+                    .width(iced::Length::Fill),
+            ),
+            None => iced::Element::from(iced::widget::vertical_space().height(0)),
+        };
+
+        let downgrade_button = button(if self.downgrade.is_some() { "Hide cached versions" } else { "Downgrade…" })
+            .on_press_maybe(
+                (!self.loading && installed && !is_aur)
+                    .then_some(AppMessage::PackageViewMessage(PackageViewMessage::Downgrade)),
+            );
+
+        let downgrade = match &self.downgrade {
+            Some(versions) if versions.is_empty() => iced::Element::from(text("No cached versions of this package")),
+            Some(versions) => iced::Element::from(
+                column![
+                    iced::widget::checkbox("Add to IgnorePkg after downgrading", self.ignore_after_downgrade)
+                        .on_toggle(|b| AppMessage::PackageViewMessage(PackageViewMessage::IgnoreAfterDowngradeToggled(b))),
+                    iced::widget::scrollable(
+                        column(versions.iter().map(|(cached, ordering)| {
+                            row![
+                                text(cached.version.clone()),
+                                text(match ordering {
+                                    Ordering::Less => "older",
+                                    Ordering::Equal => "installed",
+                                    Ordering::Greater => "newer",
+                                }),
+                                text(format_size(cached.size)),
+                                iced::widget::horizontal_space(),
+                                button("Install").on_press_maybe(
+                                    (!self.loading && *ordering != Ordering::Equal).then_some(
+                                        AppMessage::PackageViewMessage(PackageViewMessage::DowngradeTo(cached.clone()))
+                                    )
+                                ),
+                            ]
+                            .spacing(10)
+                            .align_y(iced::Alignment::Center)
+                            .into()
+                        }))
+                        .spacing(5)
+                    )
+                    .height(iced::Length::Fill),
+                ]
+                .spacing(10),
+            ),
+            None => iced::Element::from(iced::widget::vertical_space().height(0)),
+        };
+
+        let held = package_lock.is_held();
+        let hold_label = match (held, self.held_by) {
+            (true, Some(HoldScope::IgnorePkg)) => "Release hold (IgnorePkg)",
+            (true, Some(HoldScope::App)) => "Release hold (app)",
+            (true, None) => "Release hold",
+            (false, _) => "Hold",
+        };
+        let hold_button = button(hold_label).on_press_maybe(
+            (!self.loading && installed).then_some(AppMessage::PackageViewMessage(PackageViewMessage::ToggleHold)),
+        );
+        let hold_scope = iced::widget::checkbox("Hold in IgnorePkg", self.hold_system_wide)
+            .on_toggle(|b| AppMessage::PackageViewMessage(PackageViewMessage::HoldSystemWideToggled(b)));
+
         let system_update = button("Full Update").on_press(AppMessage::PackageViewMessage(PackageViewMessage::SystemUpdate));
 
+        let install_file = button("Install from file…").on_press(AppMessage::PackageViewMessage(PackageViewMessage::InstallFromFile));
+
+        //Same name packaged by other repositories, the selected package is skipped before locking
+        let other_repos = match &self.package {
+            Some(selected) => self
+                .server
+                .lock()
+                .unwrap()
+                .get_variants(package_lock.get_property("Name".to_string()).unwrap_or_default())
+                .into_iter()
+                .filter(|p| !Arc::ptr_eq(p, selected))
+                .map(|p| {
+                    let p = p.lock().unwrap();
+                    format!(
+                        "{} ({})",
+                        p.get_property("Repository".to_string()).unwrap_or_default(),
+                        p.get_property("Version".to_string()).unwrap_or_default()
+                    )
+                })
+                .collect::<Vec<_>>(),
+            None => vec![],
+        };
+
         let spinner = if self.loading {
             iced::Element::from(iced_aw::Spinner::new())
         } else {
             iced::Element::new(iced::widget::horizontal_space())
         };
 
-        return column![
+        let tab_button = |label: &'static str, tab: DisplayTab| {
+            button(label)
+                .style(if self.tab == tab { button::primary } else { button::secondary })
+                .on_press(AppMessage::PackageViewMessage(PackageViewMessage::TabSelected(tab)))
+        };
+        let tabs = row![
+            tab_button("Info", DisplayTab::Info),
+            tab_button("Files", DisplayTab::Files),
+            tab_button("History", DisplayTab::History)
+        ]
+        .spacing(10);
+
+        let info = column![
             row![
                 text("Name: "),
                 text(
@@ -169,6 +668,18 @@ impl PackageDisplay {
                         .unwrap_or_default(),
                 ),
             ],
+            row![
+                text("Repository: "),
+                text(
+                    package_lock
+                        .get_property("Repository".to_string())
+                        .unwrap_or("None (foreign)".to_string()),
+                ),
+            ],
+            row![
+                text("Also in: "),
+                text(if other_repos.is_empty() { "-".to_string() } else { other_repos.join(", ") }),
+            ],
             row![
                 text("Description: "),
                 text(
@@ -201,6 +712,22 @@ impl PackageDisplay {
                         .unwrap_or("Not installed".to_string()),
                 ),
             ],
+            row![
+                text("Packager: "),
+                text(
+                    package_lock
+                        .get_property("Packager".to_string())
+                        .unwrap_or("Unknown Packager".to_string()),
+                ),
+            ],
+            row![
+                text("Validated By: "),
+                text(
+                    package_lock
+                        .get_property("Validated By".to_string())
+                        .unwrap_or("Unknown".to_string()),
+                ),
+            ],
             row![
                 text("Version: "),
                 text(
@@ -209,7 +736,22 @@ impl PackageDisplay {
                         .unwrap_or_default()
                 )
             ],
-            row![install_button, update_button, system_update, spinner].spacing(10),
+        ]
+        .spacing(20);
+
+        let content = match self.tab {
+            DisplayTab::Info => iced::Element::from(info),
+            DisplayTab::Files => self.view_files(),
+            DisplayTab::History => self.view_history(),
+        };
+
+        return column![
+            tabs,
+            content,
+            row![install_button, update_button, downgrade_button, system_update, install_file, pkgbuild_button, spinner].spacing(10),
+            row![hold_button, hold_scope].spacing(10).align_y(iced::Alignment::Center),
+            pkgbuild,
+            downgrade,
         ]
         .spacing(20)
         .width(iced::Length::Fill);
@@ -0,0 +1,161 @@
+use iced::Task;
+use iced::widget::{Column, button, column, row, scrollable, text};
+use std::path::PathBuf;
+
+use crate::AppMessage;
+use crate::logic::pacman_conf::{PACMAN_CONF, PacmanConf};
+
+//Options shown as text fields, an empty field comments the option out
+const VALUE_OPTIONS: [&str; 5] = ["ParallelDownloads", "IgnorePkg", "IgnoreGroup", "HoldPkg", "SigLevel"];
+const FLAG_OPTIONS: [&str; 2] = ["Color", "CheckSpace"];
+
+//Shows pacman.conf parsed into options and repositories and writes the edits back as root
+#[derive(Debug, Clone)]
+pub struct PacmanConfView {
+    pub path: String,
+    pub conf: PacmanConf,
+    //Contents as last loaded, to tell whether there is anything to save
+    pub original: String,
+    pub error: Option<String>,
+    pub saving: bool,
+}
+
+impl Default for PacmanConfView {
+    fn default() -> Self {
+        PacmanConfView {
+            path: PACMAN_CONF.to_string(),
+            conf: PacmanConf::default(),
+            original: String::new(),
+            error: None,
+            saving: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PacmanConfMessage {
+    Load,
+    OptionChanged(String, String),
+    FlagToggled(String, bool),
+    RepositoryToggled(String, bool),
+    Save,
+    Saved(String),
+}
+
+impl PacmanConfView {
+    pub fn update(&mut self, message: AppMessage) -> Task<AppMessage> {
+        match message {
+            AppMessage::PacmanConfMessage(m) => match m {
+                PacmanConfMessage::Load => {
+                    match PacmanConf::load(&PathBuf::from(&self.path)) {
+                        Ok(conf) => {
+                            self.original = conf.to_string();
+                            self.conf = conf;
+                            self.error = None;
+                        }
+                        Err(e) => self.error = Some(e),
+                    }
+                    Task::none()
+                }
+                PacmanConfMessage::OptionChanged(key, value) => {
+                    self.conf.set_option(&key, (!value.trim().is_empty()).then_some(value));
+                    Task::none()
+                }
+                PacmanConfMessage::FlagToggled(key, enabled) => {
+                    self.conf.set_option(&key, enabled.then_some(String::new()));
+                    Task::none()
+                }
+                PacmanConfMessage::RepositoryToggled(name, enabled) => {
+                    self.conf.set_repository_enabled(&name, enabled);
+                    Task::none()
+                }
+                PacmanConfMessage::Save => {
+                    let conf = self.conf.clone();
+                    let path = PathBuf::from(&self.path);
+                    self.saving = true;
+                    Task::perform(async move { conf.save(&path) }, |stderror| {
+                        AppMessage::PacmanConfMessage(PacmanConfMessage::Saved(stderror))
+                    })
+                }
+                PacmanConfMessage::Saved(stderror) => {
+                    self.saving = false;
+                    if !stderror.is_empty() {
+                        let _ = native_dialog::MessageDialog::new().set_text(&stderror).set_title("An error has ocurred :(").show_alert();
+                        return Task::none();
+                    }
+                    self.update(AppMessage::PacmanConfMessage(PacmanConfMessage::Load))
+                }
+            },
+            _ => Task::none(),
+        }
+    }
+
+    pub fn view(&self) -> Column<'_, AppMessage> {
+        let options = column(VALUE_OPTIONS.iter().map(|key| {
+            //The raw value keeps the trailing space while typing, unless the key is split over several lines
+            let raw = self.conf.option(key).unwrap_or_default();
+            let values = self.conf.values("options", key).join(" ");
+            let value = if raw.split_whitespace().collect::<Vec<_>>().join(" ") == values { raw } else { values };
+            row![
+                text(*key).width(150),
+                iced::widget::text_input("not set", &value)
+                    .on_input(move |v| AppMessage::PacmanConfMessage(PacmanConfMessage::OptionChanged(key.to_string(), v))),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center)
+            .into()
+        }))
+        .push(row(FLAG_OPTIONS.iter().map(|key| {
+            iced::widget::checkbox(*key, self.conf.option(key).is_some())
+                .on_toggle(move |b| AppMessage::PacmanConfMessage(PacmanConfMessage::FlagToggled(key.to_string(), b)))
+                .into()
+        }))
+        .spacing(20))
+        .spacing(5);
+
+        let repositories = column(self.conf.repositories().into_iter().map(|r| {
+            let name = r.name.clone();
+            column![
+                iced::widget::checkbox(format!("[{}]", r.name), r.enabled)
+                    .on_toggle(move |b| AppMessage::PacmanConfMessage(PacmanConfMessage::RepositoryToggled(name.clone(), b))),
+                column(
+                    r.includes
+                        .iter()
+                        .map(|i| format!("Include = {}", i))
+                        .chain(r.servers.iter().map(|s| format!("Server = {}", s)))
+                        .chain(r.sig_level.iter().map(|s| format!("SigLevel = {}", s)))
+                        .map(|l| text(l).size(12).font(iced::Font::MONOSPACE).into())
+                )
+                .padding([0, 30]),
+            ]
+            .spacing(2)
+            .into()
+        }))
+        .spacing(8);
+
+        let validation = match (&self.error, self.conf.validate()) {
+            (Some(e), _) => text(e.clone()).style(text::danger),
+            (None, Err(e)) => text(e).style(text::danger),
+            (None, Ok(())) if self.conf.to_string() != self.original => text("Unsaved changes"),
+            (None, Ok(())) => text("No changes"),
+        };
+        let can_save = !self.saving && self.error.is_none() && self.conf.validate().is_ok() && self.conf.to_string() != self.original;
+
+        column![
+            row![
+                text(self.path.clone()),
+                iced::widget::horizontal_space(),
+                button("Reload").on_press_maybe((!self.saving).then_some(AppMessage::PacmanConfMessage(PacmanConfMessage::Load))),
+                button("Save").on_press_maybe(can_save.then_some(AppMessage::PacmanConfMessage(PacmanConfMessage::Save))),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            validation,
+            text("Options").size(20),
+            options,
+            text("Repositories").size(20),
+            scrollable(repositories).height(iced::Length::Fill),
+        ]
+        .spacing(10)
+    }
+}
@@ -0,0 +1,193 @@
+use iced::Task;
+use iced::widget::{Column, button, column, row, scrollable, text};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::AppMessage;
+use crate::logic::hold;
+use crate::logic::package::Package;
+use crate::logic::pacman_conf::{self, PACMAN_CONF, PacmanConf, Repository};
+use crate::logic::profile::{self, Drift, Profile};
+use crate::logic::server::Server;
+use crate::ui::package_display::PackageViewMessage;
+use crate::ui::pacman_conf::PacmanConfMessage;
+use crate::ui::transaction::TransactionMessage;
+
+//Dashboard of the drift between the declarative profile and the live system
+#[derive(Debug, Clone)]
+pub struct ProfileView {
+    pub server: Arc<Mutex<Server>>,
+    pub path: String,
+    pub state: Option<Result<(Profile, Drift), String>>,
+    pub loading: bool,
+}
+
+impl Default for ProfileView {
+    fn default() -> Self {
+        ProfileView {
+            server: Arc::default(),
+            path: profile::profile_file().to_string_lossy().to_string(),
+            state: None,
+            loading: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ProfileMessage {
+    Check,
+    Checked(Result<(Profile, Drift), String>),
+    CreateFromSystem,
+    RemoveExtraToggled(bool),
+    Saved(Result<(), String>),
+    Apply,
+}
+
+//Installed packages, held patterns and configured repositories
+type LiveState = (Vec<Package>, Vec<String>, Vec<Repository>);
+
+fn live_state(server: &Arc<Mutex<Server>>) -> Result<LiveState, String> {
+    let conf = Path::new(PACMAN_CONF);
+    let packages = server.lock().unwrap().installed();
+    let mut held = pacman_conf::options(conf, "IgnorePkg");
+    held.extend(hold::load(&hold::hold_file()));
+    Ok((packages, held, PacmanConf::load(conf)?.repositories()))
+}
+
+impl ProfileView {
+    //Shown on the tab button so drift is visible from every tab
+    pub fn tab_label(&self) -> String {
+        match &self.state {
+            Some(Ok((_, drift))) if drift.count() > 0 => format!("Profile ({})", drift.count()),
+            _ => "Profile".to_string(),
+        }
+    }
+
+    pub fn update(&mut self, message: AppMessage) -> Task<AppMessage> {
+        match message {
+            //Anything that installs, removes or holds packages can change the drift
+            AppMessage::TransactionMessage(TransactionMessage::Finished(_))
+            | AppMessage::PackageViewMessage(
+                PackageViewMessage::FinishedSystemUpdate(_) | PackageViewMessage::HoldChanged(..),
+            )
+            | AppMessage::PacmanConfMessage(PacmanConfMessage::Saved(_)) => self.update(AppMessage::ProfileMessage(ProfileMessage::Check)),
+            AppMessage::ProfileMessage(m) => match m {
+                ProfileMessage::Check => {
+                    self.loading = true;
+                    let path = PathBuf::from(&self.path);
+                    let server = self.server.clone();
+                    Task::perform(
+                        async move {
+                            let profile = profile::load(&path)?;
+                            let (packages, held, repositories) = live_state(&server)?;
+                            let drift = profile.drift(&packages, &held, &repositories);
+                            Ok((profile, drift))
+                        },
+                        |r| AppMessage::ProfileMessage(ProfileMessage::Checked(r)),
+                    )
+                }
+                ProfileMessage::Checked(state) => {
+                    self.loading = false;
+                    self.state = Some(state);
+                    Task::none()
+                }
+                ProfileMessage::CreateFromSystem => {
+                    self.loading = true;
+                    let path = PathBuf::from(&self.path);
+                    let server = self.server.clone();
+                    Task::perform(
+                        async move {
+                            let (packages, held, repositories) = live_state(&server)?;
+                            profile::save(&path, &Profile::from_system(&packages, &held, &repositories))
+                        },
+                        |r| AppMessage::ProfileMessage(ProfileMessage::Saved(r)),
+                    )
+                }
+                ProfileMessage::RemoveExtraToggled(remove_extra) => {
+                    let result = profile::set_remove_extra(Path::new(&self.path), remove_extra);
+                    self.update(AppMessage::ProfileMessage(ProfileMessage::Saved(result)))
+                }
+                ProfileMessage::Saved(result) => {
+                    if let Err(e) = result {
+                        self.loading = false;
+                        let _ = native_dialog::MessageDialog::new().set_text(&e).set_title("An error has ocurred :(").show_alert();
+                        return Task::none();
+                    }
+                    self.update(AppMessage::ProfileMessage(ProfileMessage::Check))
+                }
+                ProfileMessage::Apply => {
+                    let Some(Ok((profile, drift))) = &self.state else {
+                        return Task::none();
+                    };
+                    Task::done(AppMessage::TransactionMessage(TransactionMessage::Request(drift.transaction(profile))))
+                }
+            },
+            _ => Task::none(),
+        }
+    }
+
+    fn section<'a>(title: &str, entries: &[String]) -> Column<'a, AppMessage> {
+        column![
+            text(format!("{} ({})", title, entries.len())),
+            column(entries.iter().map(|x| text(x.clone()).size(12).into())).spacing(2),
+        ]
+        .spacing(5)
+    }
+
+    pub fn view(&self) -> Column<'_, AppMessage> {
+        let body: iced::Element<AppMessage> = match &self.state {
+            _ if self.loading => iced_aw::Spinner::new().circle_radius(20.0).into(),
+            None => text("").into(),
+            Some(Err(e)) => column![
+                text(e.clone()).style(text::danger),
+                button("Create profile from this system").on_press(AppMessage::ProfileMessage(ProfileMessage::CreateFromSystem)),
+            ]
+            .spacing(10)
+            .into(),
+            Some(Ok((profile, drift))) => column![
+                text(format!(
+                    "{} package(s), {} hold(s) and {} repositories in the profile",
+                    profile.packages.len(),
+                    profile.holds.len(),
+                    profile.repositories.len()
+                )),
+                iced::widget::checkbox("Remove explicit packages outside the profile", profile.remove_extra)
+                    .on_toggle(|b| AppMessage::ProfileMessage(ProfileMessage::RemoveExtraToggled(b))),
+                if drift.count() == 0 {
+                    text("The system matches the profile")
+                } else {
+                    text(format!("{} difference(s) with the profile", drift.count())).style(text::danger)
+                },
+                scrollable(
+                    column![
+                        Self::section("Not installed", &drift.missing),
+                        Self::section("Installed outside the profile", &drift.extra),
+                        Self::section("Not held", &drift.unheld),
+                        Self::section("Disabled repositories", &drift.disabled_repositories),
+                        Self::section("Repositories missing from pacman.conf", &drift.unknown_repositories),
+                    ]
+                    .spacing(20),
+                )
+                .height(iced::Length::Fill),
+            ]
+            .spacing(10)
+            .into(),
+        };
+
+        //Unknown repositories, and extra packages while they are kept, leave nothing to apply
+        let can_apply = !self.loading && matches!(&self.state, Some(Ok((profile, drift))) if !drift.transaction(profile).is_empty());
+
+        column![
+            row![
+                text(self.path.clone()),
+                iced::widget::horizontal_space(),
+                button("Check").on_press_maybe((!self.loading).then_some(AppMessage::ProfileMessage(ProfileMessage::Check))),
+                button("Apply profile").on_press_maybe(can_apply.then_some(AppMessage::ProfileMessage(ProfileMessage::Apply))),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            body,
+        ]
+        .spacing(10)
+    }
+}
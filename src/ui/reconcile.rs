@@ -0,0 +1,175 @@
+use iced::Task;
+use iced::widget::{Column, button, column, row, scrollable, text};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::AppMessage;
+use crate::logic::cache;
+use crate::logic::pacman_conf::PACMAN_CONF;
+use crate::logic::reconcile::{self, Reconciliation};
+use crate::logic::server::Server;
+use crate::logic::transaction::Transaction;
+use crate::ui::transaction::TransactionMessage;
+
+//Compares the system with an imported package list and offers the transactions that converge them
+#[derive(Default, Debug, Clone)]
+pub struct ReconcileView {
+    pub server: Arc<Mutex<Server>>,
+    pub path: String,
+    pub result: Option<Result<Reconciliation, String>>,
+    pub loading: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum ReconcileMessage {
+    PathChanged(String),
+    Browse,
+    Load,
+    Loaded(Result<Reconciliation, String>),
+    InstallMissing,
+    RemoveExtra,
+    AlignVersions,
+}
+
+impl ReconcileView {
+    fn request(transaction: Transaction) -> Task<AppMessage> {
+        Task::done(AppMessage::TransactionMessage(TransactionMessage::Request(transaction)))
+    }
+
+    pub fn update(&mut self, message: AppMessage) -> Task<AppMessage> {
+        match message {
+            //The installed set changed, compare again against the same list
+            AppMessage::TransactionMessage(TransactionMessage::Finished(_)) if self.result.is_some() => {
+                self.update(AppMessage::ReconcileMessage(ReconcileMessage::Load))
+            }
+            AppMessage::ReconcileMessage(m) => match m {
+                ReconcileMessage::PathChanged(path) => {
+                    self.path = path;
+                    Task::none()
+                }
+                ReconcileMessage::Browse => {
+                    let path = native_dialog::FileDialog::new()
+                        .set_title("Import package list")
+                        .add_filter("Package list", &["json", "txt"])
+                        .show_open_single_file()
+                        .unwrap_or_default();
+                    let Some(path) = path else {
+                        return Task::none();
+                    };
+                    self.path = path.to_string_lossy().to_string();
+                    self.update(AppMessage::ReconcileMessage(ReconcileMessage::Load))
+                }
+                ReconcileMessage::Load => {
+                    if self.path.is_empty() {
+                        return Task::none();
+                    }
+                    self.loading = true;
+                    let path = PathBuf::from(&self.path);
+                    let server = self.server.clone();
+                    Task::perform(
+                        async move {
+                            let wanted = reconcile::load(&path)?;
+                            let packages = server.lock().unwrap().installed();
+                            Ok(reconcile::reconcile(&wanted, &packages))
+                        },
+                        |r| AppMessage::ReconcileMessage(ReconcileMessage::Loaded(r)),
+                    )
+                }
+                ReconcileMessage::Loaded(result) => {
+                    self.loading = false;
+                    self.result = Some(result);
+                    Task::none()
+                }
+                ReconcileMessage::InstallMissing => match &self.result {
+                    Some(Ok(result)) => Self::request(result.install_missing()),
+                    _ => Task::none(),
+                },
+                ReconcileMessage::RemoveExtra => match &self.result {
+                    Some(Ok(result)) => Self::request(result.remove_extra()),
+                    _ => Task::none(),
+                },
+                ReconcileMessage::AlignVersions => {
+                    let Some(Ok(result)) = &self.result else {
+                        return Task::none();
+                    };
+                    let transaction = result.align_versions(&cache::scan(&cache::cache_dirs(Path::new(PACMAN_CONF))));
+                    if transaction.is_empty() {
+                        let _ = native_dialog::MessageDialog::new()
+                            .set_text(&transaction.notes.join("\n"))
+                            .set_title("No cached versions")
+                            .show_alert();
+                        return Task::none();
+                    }
+                    Self::request(transaction)
+                }
+            },
+            _ => Task::none(),
+        }
+    }
+
+    fn section<'a>(title: String, entries: Vec<String>, action: &'static str, message: ReconcileMessage) -> Column<'a, AppMessage> {
+        column![
+            row![
+                text(title),
+                iced::widget::horizontal_space(),
+                button(action).on_press_maybe((!entries.is_empty()).then_some(AppMessage::ReconcileMessage(message))),
+            ]
+            .align_y(iced::Alignment::Center),
+            column(entries.into_iter().map(|x| text(x).size(12).into())).spacing(2),
+        ]
+        .spacing(5)
+    }
+
+    pub fn view(&self) -> Column<'_, AppMessage> {
+        let body: iced::Element<AppMessage> = match &self.result {
+            _ if self.loading => iced_aw::Spinner::new().circle_radius(20.0).into(),
+            None => text("Import a list exported from another machine, as JSON or one name per line").into(),
+            Some(Err(e)) => text(e.clone()).style(text::danger).into(),
+            Some(Ok(result)) if result.is_empty() => text("The system matches the list").into(),
+            Some(Ok(result)) => scrollable(
+                column![
+                    Self::section(
+                        format!("Missing ({})", result.missing.len()),
+                        result.missing.iter().map(|w| w.name.clone()).collect(),
+                        "Install missing",
+                        ReconcileMessage::InstallMissing,
+                    ),
+                    Self::section(
+                        format!("Extra ({})", result.extra.len()),
+                        result.extra.clone(),
+                        "Remove extra",
+                        ReconcileMessage::RemoveExtra,
+                    ),
+                    Self::section(
+                        format!("Version differs ({})", result.differs.len()),
+                        result
+                            .differs
+                            .iter()
+                            .map(|d| format!("{}: {} installed, {} wanted", d.name, d.installed, d.wanted))
+                            .collect(),
+                        "Install wanted versions",
+                        ReconcileMessage::AlignVersions,
+                    ),
+                ]
+                .spacing(20),
+            )
+            .height(iced::Length::Fill)
+            .into(),
+        };
+
+        column![
+            row![
+                iced::widget::text_input("package list", &self.path)
+                    .on_input(|x| AppMessage::ReconcileMessage(ReconcileMessage::PathChanged(x)))
+                    .on_submit(AppMessage::ReconcileMessage(ReconcileMessage::Load)),
+                button("Browse").on_press_maybe((!self.loading).then_some(AppMessage::ReconcileMessage(ReconcileMessage::Browse))),
+                button("Compare").on_press_maybe(
+                    (!self.loading && !self.path.is_empty()).then_some(AppMessage::ReconcileMessage(ReconcileMessage::Load))
+                ),
+            ]
+            .spacing(10),
+            body,
+        ]
+        .spacing(10)
+    }
+}
@@ -0,0 +1,144 @@
+use iced::Task;
+use iced::widget::{Column, button, column, row, scrollable, text};
+use iced_aw::Spinner;
+use std::sync::{Arc, Mutex};
+
+use crate::AppMessage;
+use crate::logic::server::Server;
+use crate::ui::package_button::PackageButton;
+
+const PAGE_SIZE: usize = 100;
+
+//Lists every configured repository and lets the user browse the packages inside each one
+#[derive(Default, Debug, Clone)]
+pub struct RepositoryBrowser {
+    pub server: Arc<Mutex<Server>>,
+    pub repositories: Vec<(String, usize)>,
+    pub selected: Option<String>,
+    pub packages: Vec<PackageButton>,
+    pub loading: bool,
+    pub page: i32,
+}
+
+#[derive(Debug, Clone)]
+pub enum RepositoryMessage {
+    Load,
+    Selected(String),
+    Loaded(Vec<PackageButton>),
+    PageUp,
+    PageDown,
+}
+
+impl RepositoryBrowser {
+    fn get_total_pages(&self) -> i32 {
+        (self.packages.len() / PAGE_SIZE) as i32
+    }
+
+    pub fn update(&mut self, message: AppMessage) -> Task<AppMessage> {
+        match message {
+            AppMessage::RepositoryMessage(m) => match m {
+                RepositoryMessage::Load => {
+                    self.repositories = self.server.lock().unwrap().get_repository_counts();
+                    Task::none()
+                }
+                RepositoryMessage::Selected(repo) => {
+                    self.selected = Some(repo.clone());
+                    self.loading = true;
+                    self.packages.clear();
+                    self.page = 0;
+                    let server = self.server.clone();
+                    Task::perform(
+                        async move {
+                            server
+                                .lock()
+                                .unwrap()
+                                .get_repository_packages(repo)
+                                .into_iter()
+                                .map(|package| PackageButton { package })
+                                .collect()
+                        },
+                        |p| AppMessage::RepositoryMessage(RepositoryMessage::Loaded(p)),
+                    )
+                }
+                RepositoryMessage::Loaded(packages) => {
+                    self.packages = packages;
+                    self.loading = false;
+                    Task::none()
+                }
+                RepositoryMessage::PageUp => {
+                    self.page += 1;
+                    if self.page > self.get_total_pages() {
+                        self.page = 0
+                    };
+                    Task::none()
+                }
+                RepositoryMessage::PageDown => {
+                    self.page -= 1;
+                    if self.page < 0 {
+                        self.page = self.get_total_pages()
+                    };
+                    Task::none()
+                }
+            },
+            _ => Task::none(),
+        }
+    }
+
+    pub fn view(&self) -> Column<'_, AppMessage> {
+        let repositories = row(self
+            .repositories
+            .iter()
+            .map(|(repo, count)| {
+                button(text(format!("{} ({})", repo, count)))
+                    .style(if self.selected.as_ref() == Some(repo) {
+                        button::primary
+                    } else {
+                        button::secondary
+                    })
+                    .on_press(AppMessage::RepositoryMessage(RepositoryMessage::Selected(
+                        repo.clone(),
+                    )))
+                    .into()
+            }))
+        .spacing(10)
+        .wrap();
+
+        let packages: iced::Element<AppMessage> = if self.loading {
+            Spinner::new()
+                .width(iced::Length::Fill)
+                .height(iced::Length::Fill)
+                .circle_radius(20.0)
+                .into()
+        } else {
+            scrollable(
+                column(
+                    self.packages[std::cmp::min(self.page as usize * PAGE_SIZE, self.packages.len())
+                        ..std::cmp::min((self.page + 1) as usize * PAGE_SIZE, self.packages.len())]
+                        .iter()
+                        .map(|x| x.view().into()),
+                )
+                .spacing(10),
+            )
+            .width(iced::Length::Fill)
+            .into()
+        };
+
+        column![
+            repositories,
+            row![
+                text(match &self.selected {
+                    Some(repo) => format!("{} package(s) in {}", self.packages.len(), repo),
+                    None => "Select a repository".to_string(),
+                }),
+                iced::widget::horizontal_space(),
+                button("<").on_press(AppMessage::RepositoryMessage(RepositoryMessage::PageDown)),
+                text(format!("{}/{}", self.page, self.get_total_pages())),
+                button(">").on_press(AppMessage::RepositoryMessage(RepositoryMessage::PageUp)),
+            ]
+            .spacing(5)
+            .align_y(iced::Alignment::Center),
+            packages
+        ]
+        .spacing(10)
+    }
+}
@@ -6,6 +6,8 @@ use std::cmp::Reverse;
 use std::sync::{Arc, Mutex};
 
 use crate::AppMessage;
+use crate::logic::export::{self, ExportColumn, ExportFormat, ExportOptions};
+use crate::logic::filter::{FacetCounts, Filter, InstalledFacet, ReasonFacet};
 use crate::logic::server::Server;
 use crate::ui::package_button::PackageButton;
 
@@ -18,9 +20,21 @@ pub struct SearchWidget {
     pub packages: Vec<PackageButton>,
     pub loading: bool,
     pub page: i32,
-    pub filter: FilterState,
+    pub filter: Filter,
+    pub counts: FacetCounts,
     pub sorter: SorterState,
-    pub search_handle: Option<Handle>
+    pub search_handle: Option<Handle>,
+    pub include_aur: bool,
+    pub mode: SearchMode,
+    pub export: ExportOptions,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Copy)]
+pub enum SearchMode {
+    #[default]
+    Packages,
+    //Query is a path or command, results are its owner and the packages providing it
+    FileOwner,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Copy)]
@@ -31,23 +45,39 @@ pub enum SorterState {
     InstallDate,
 }
 
-#[derive(Debug, Clone, Default, PartialEq, Eq, Copy)]
-pub enum FilterState {
-    #[default]
-    All,
-    Installed,
-    NotInstalled,
+//Entry of a facet pick list, `None` stands for "any value"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FacetOption {
+    pub value: Option<String>,
+    pub count: usize,
+}
+
+impl std::fmt::Display for FacetOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.value {
+            Some(v) => write!(f, "{} ({})", v, self.count),
+            None => write!(f, "Any"),
+        }
+    }
 }
 
 #[derive(Debug, Clone,)]
 pub enum SearchMessage {
     SearchChanged(String),
     SearchSubmited,
-    SearchFinished(Vec<PackageButton>),
-    FilterChanged(FilterState),
+    SearchFinished(Vec<PackageButton>, FacetCounts),
+    FilterChanged(Filter),
     SorterChanged(SorterState),
+    AurToggled(bool),
+    ModeChanged(SearchMode),
+    CheckAurUpdates,
+    AurUpdatesChecked(Result<usize, String>),
     PageUp,
     PageDown,
+    ExportFormatChanged(ExportFormat),
+    ExportColumnToggled(ExportColumn, bool),
+    //Exports the current results, or every installed package when true
+    Export(bool),
 }
 
 impl SearchWidget {
@@ -55,23 +85,45 @@ impl SearchWidget {
         return (self.packages.len() / PAGE_SIZE) as i32;
     }
 
-    pub fn handle_search(&self) -> Vec<PackageButton> {
+    pub fn handle_search(&self) -> (Vec<PackageButton>, FacetCounts) {
     	if self.server.is_poisoned() {self.server.clear_poison();}
 
      	let mut packages = {
       		let server_lock = self.server.lock().unwrap(); //Lock is aquired
-        	server_lock.search(self.search.clone())
+            match self.mode {
+                SearchMode::Packages => server_lock.search(self.search.clone()),
+                SearchMode::FileOwner => {
+                    //The installed owner comes first, followed by uninstalled packages shipping the same file
+                    let mut found = server_lock.owner_of(self.search.clone()).into_iter().collect::<Vec<_>>();
+                    let providers = server_lock
+                        .search_files(self.search.clone())
+                        .into_iter()
+                        .filter(|p| !p.lock().unwrap().is_installed())
+                        .collect::<Vec<_>>();
+                    found.extend(providers);
+                    found
+                }
+            }
             //Lock is dropped inmediatly as to avoid deadlocks
       	};
      	//println!("Succesfully returned to main thread");
 
-        packages = packages
-            .into_iter()
-            .filter(|x| {match self.filter {
-            	FilterState::All => true,
-             	FilterState::Installed => x.lock().unwrap().get_property("Installed".to_string()).unwrap() == "True".to_string(),
-              	FilterState::NotInstalled => x.lock().unwrap().get_property("Installed".to_string()).unwrap() != "True".to_string()
-            }}).collect();
+        if self.include_aur && self.mode == SearchMode::Packages {
+            let aur = self.server.lock().unwrap().search_aur(self.search.clone());
+            match aur {
+                Ok(aur) => {
+                    //Installed AUR packages are already part of the local results
+                    let aur = aur.into_iter().filter(|x| !packages.iter().any(|p| Arc::ptr_eq(p, x))).collect::<Vec<_>>();
+                    packages.extend(aur)
+                }
+                Err(e) => println!("AUR search failed: {}", e),
+            }
+        }
+
+        //Counts are taken before filtering so every option shows how many results it would keep
+        let counts = FacetCounts::from_packages(&packages);
+
+        packages.retain(|x| self.filter.matches(&x.lock().unwrap()));
 
         //println!("SUccesfully filtered packages");
 
@@ -87,7 +139,7 @@ impl SearchWidget {
 
         //println!("Sucessfully generated widgets. Returning");
 
-        return packages_widgets
+        return (packages_widgets, counts)
     }
 
     pub fn update(&mut self, message: AppMessage) -> Task<AppMessage> {
@@ -106,15 +158,16 @@ impl SearchWidget {
                     self.page = 0;
                     println!("Search started");
 
-                    let (task, handle) =  iced::Task::abortable(iced::Task::perform(async move { this.handle_search() }, |p| {
-                        AppMessage::SearchMessage(SearchMessage::SearchFinished(p))
+                    let (task, handle) =  iced::Task::abortable(iced::Task::perform(async move { this.handle_search() }, |(p, counts)| {
+                        AppMessage::SearchMessage(SearchMessage::SearchFinished(p, counts))
                     }));
                     self.search_handle = Some(handle);
                     return task
                 }
-                SearchMessage::SearchFinished(packages) => {
+                SearchMessage::SearchFinished(packages, counts) => {
                     println!("Search finished. Rendering...");
                     self.packages = packages;
+                    self.counts = counts;
                     self.loading = false;
                     if self.search_handle.is_some() {self.search_handle.as_mut().unwrap().abort(); self.search_handle = None;}
                     iced::Task::none()
@@ -141,12 +194,167 @@ impl SearchWidget {
                 SearchMessage::SorterChanged(s) => {
                 	self.sorter = s;
                 	self.update(AppMessage::SearchMessage(SearchMessage::SearchSubmited))
+                },
+                SearchMessage::ModeChanged(mode) => {
+                    self.mode = mode;
+                    self.update(AppMessage::SearchMessage(SearchMessage::SearchSubmited))
+                }
+                SearchMessage::AurToggled(b) => {
+                    self.include_aur = b;
+                    self.update(AppMessage::SearchMessage(SearchMessage::SearchSubmited))
+                }
+                SearchMessage::CheckAurUpdates => {
+                    let server = self.server.clone();
+                    Task::perform(async move { server.lock().unwrap().check_aur_updates() }, |r| {
+                        AppMessage::SearchMessage(SearchMessage::AurUpdatesChecked(r))
+                    })
+                }
+                SearchMessage::ExportFormatChanged(format) => {
+                    self.export.format = format;
+                    Task::none()
+                }
+                SearchMessage::ExportColumnToggled(column, enabled) => {
+                    self.export.set_column(column, enabled);
+                    Task::none()
+                }
+                SearchMessage::Export(installed) => {
+                    let packages = if installed {
+                        let mut packages = self.server.lock().unwrap().installed();
+                        packages.sort_by_key(|p| p.get_property("Name".to_string()));
+                        packages
+                    } else {
+                        //Same order and filtering as the list on screen
+                        self.packages.iter().map(|x| x.package.lock().unwrap().clone()).collect()
+                    };
+                    let filename = format!("packages.{}", self.export.format.extension());
+                    let path = native_dialog::FileDialog::new()
+                        .set_title("Export packages")
+                        .set_filename(&filename)
+                        .show_save_single_file()
+                        .unwrap_or_default();
+                    if let Some(path) = path
+                        && let Err(e) = export::write(&path, &packages, &self.export.columns, self.export.format)
+                    {
+                        let _ = native_dialog::MessageDialog::new().set_text(&e).set_title("An error has ocurred :(").show_alert();
+                    }
+                    Task::none()
+                }
+                SearchMessage::AurUpdatesChecked(result) => {
+                    match result {
+                        Ok(count) => {
+                            let _ = native_dialog::MessageDialog::new().set_title("AUR updates").set_text(&format!("{} AUR package(s) can be updated", count)).show_alert();
+                        }
+                        Err(e) => {
+                            let _ = native_dialog::MessageDialog::new().set_text(&e).set_title("An error has ocurred :(").show_alert();
+                        }
+                    }
+                    self.update(AppMessage::SearchMessage(SearchMessage::SearchSubmited))
                 }
             },
             _ => iced::Task::none(),
         }
     }
 
+    fn view_filters(&self) -> Column<'_, AppMessage> {
+        let counts = &self.counts;
+        let changed = |f: Filter| AppMessage::SearchMessage(SearchMessage::FilterChanged(f));
+
+        let with_installed = |state: InstalledFacet| {
+            let mut f = self.filter.clone();
+            f.installed = state;
+            changed(f)
+        };
+        let installed = row![
+            iced::widget::radio("All", InstalledFacet::Any, Some(self.filter.installed), with_installed),
+            iced::widget::radio(format!("Installed ({})", counts.installed), InstalledFacet::Installed, Some(self.filter.installed), with_installed),
+            iced::widget::radio(format!("Not Installed ({})", counts.not_installed), InstalledFacet::NotInstalled, Some(self.filter.installed), with_installed),
+        ].spacing(5);
+
+        let with_reason = |state: ReasonFacet| {
+            let mut f = self.filter.clone();
+            f.reason = state;
+            changed(f)
+        };
+        let reason = row![
+            iced::widget::radio("Any reason", ReasonFacet::Any, Some(self.filter.reason), with_reason),
+            iced::widget::radio(format!("Explicit ({})", counts.explicit), ReasonFacet::Explicit, Some(self.filter.reason), with_reason),
+            iced::widget::radio(format!("Dependency ({})", counts.dependency), ReasonFacet::Dependency, Some(self.filter.reason), with_reason),
+        ].spacing(5);
+
+        let repositories = iced::widget::Row::with_children(counts.repositories.iter().map(|(repo, count)| {
+            let repo = repo.clone();
+            let filter = self.filter.clone();
+            iced::widget::checkbox(format!("{} ({})", repo, count), self.filter.repositories.contains(&repo))
+                .on_toggle(move |checked| {
+                    let mut f = filter.clone();
+                    if checked { f.repositories.insert(repo.clone()); } else { f.repositories.remove(&repo); }
+                    changed(f)
+                })
+                .into()
+        })).spacing(10).wrap();
+
+        let toggle = |label: &str, count: usize, checked: bool, set: fn(&mut Filter, bool)| {
+            let filter = self.filter.clone();
+            iced::widget::checkbox(format!("{} ({})", label, count), checked).on_toggle(move |c| {
+                let mut f = filter.clone();
+                set(&mut f, c);
+                changed(f)
+            })
+        };
+        let flags = row![
+            toggle("Upgradable", counts.upgradable, self.filter.upgradable, |f, c| f.upgradable = c),
+            toggle("Orphan", counts.orphan, self.filter.orphan, |f, c| f.orphan = c),
+            toggle("Foreign (AUR/local)", counts.foreign, self.filter.foreign, |f, c| f.foreign = c),
+        ].spacing(10);
+
+        let picker = |label: &'static str, values: &std::collections::BTreeMap<String, usize>, selected: &Option<String>, set: fn(&mut Filter, Option<String>)| {
+            let mut options = vec![FacetOption { value: None, count: 0 }];
+            options.extend(values.iter().map(|(v, c)| FacetOption { value: Some(v.clone()), count: *c }));
+            let current = options.iter().find(|x| &x.value == selected).cloned();
+            let filter = self.filter.clone();
+            row![
+                text(label),
+                iced::widget::pick_list(options, current, move |o: FacetOption| {
+                    let mut f = filter.clone();
+                    set(&mut f, o.value);
+                    changed(f)
+                })
+            ].spacing(5).align_y(iced::Alignment::Center)
+        };
+        let pickers = row![
+            picker("Group", &counts.groups, &self.filter.group, |f, v| f.group = v),
+            picker("Arch", &counts.architectures, &self.filter.architecture, |f, v| f.architecture = v),
+            picker("License", &counts.licenses, &self.filter.license, |f, v| f.license = v),
+        ].spacing(10);
+
+        column![installed, reason, repositories, flags, pickers].spacing(5)
+    }
+
+    fn view_export(&self) -> iced::Element<'_, AppMessage> {
+        let columns = ExportColumn::ALL.into_iter().map(|c| {
+            iced::widget::checkbox(c.to_string(), self.export.columns.contains(&c))
+                .on_toggle(move |b| AppMessage::SearchMessage(SearchMessage::ExportColumnToggled(c, b)))
+                .into()
+        });
+        let names_only = self.export.format == ExportFormat::Names;
+        row![
+            text("Export: "),
+            iced::widget::pick_list(ExportFormat::ALL, Some(self.export.format), |f| AppMessage::SearchMessage(SearchMessage::ExportFormatChanged(f))),
+        ]
+        //The name list has no columns to choose
+        .extend(if names_only { vec![] } else { columns.collect::<Vec<_>>() })
+        .push(button("Results").on_press_maybe(
+            (!self.loading && (names_only || !self.export.columns.is_empty())).then_some(AppMessage::SearchMessage(SearchMessage::Export(false))),
+        ))
+        .push(button("Installed").on_press_maybe(
+            (names_only || !self.export.columns.is_empty()).then_some(AppMessage::SearchMessage(SearchMessage::Export(true))),
+        ))
+        .spacing(10)
+        .align_y(iced::Alignment::Center)
+        .wrap()
+        .into()
+    }
+
     pub fn view(&self) -> Column<AppMessage> {
         let packages = scrollable(
             column(
@@ -174,11 +382,7 @@ impl SearchWidget {
             )
         };
 
-        let filter_selector = row![
-        	iced::widget::radio("All", FilterState::All, Some(self.filter), |state| AppMessage::SearchMessage(SearchMessage::FilterChanged(state))),
-         	iced::widget::radio("Installed", FilterState::Installed, Some(self.filter), |state| AppMessage::SearchMessage(SearchMessage::FilterChanged(state))),
-          	iced::widget::radio("Not Installed", FilterState::NotInstalled, Some(self.filter), |state| AppMessage::SearchMessage(SearchMessage::FilterChanged(state)))
-        ].spacing(5);
+        let filter_selector = self.view_filters();
 
         let sorter_selector = row![
         	iced::widget::radio("Default", SorterState::Default, Some(self.sorter), |state| AppMessage::SearchMessage(SearchMessage::SorterChanged(state))),
@@ -188,14 +392,23 @@ impl SearchWidget {
 
         column![
             row![
-                iced::widget::text_input("search", &self.search)
+                iced::widget::text_input(if self.mode == SearchMode::FileOwner { "/path/to/file or command" } else { "search" }, &self.search)
                     .on_input(|x| AppMessage::SearchMessage(SearchMessage::SearchChanged(x)))
                     .on_submit(AppMessage::SearchMessage(SearchMessage::SearchSubmited)),
                 button("<").on_press(AppMessage::SearchMessage(SearchMessage::PageDown)),
                 text(format!("{}/{}", self.page, self.get_total_pages())),
                 button(">").on_press(AppMessage::SearchMessage(SearchMessage::PageUp)),
             ],
-            column![row![text("Filter by: "),filter_selector], row![text("Sort by: "), sorter_selector]].spacing(5),
+            row![
+                text("Search in: "),
+                iced::widget::radio("Packages", SearchMode::Packages, Some(self.mode), |m| AppMessage::SearchMessage(SearchMessage::ModeChanged(m))),
+                iced::widget::radio("File owner", SearchMode::FileOwner, Some(self.mode), |m| AppMessage::SearchMessage(SearchMessage::ModeChanged(m))),
+                iced::widget::horizontal_space().width(20),
+                iced::widget::checkbox("Include AUR", self.include_aur).on_toggle(|b| AppMessage::SearchMessage(SearchMessage::AurToggled(b))),
+                button("Check AUR updates").on_press(AppMessage::SearchMessage(SearchMessage::CheckAurUpdates)),
+            ].spacing(10).align_y(iced::Alignment::Center),
+            column![column![text("Filter by: "),filter_selector].spacing(5), row![text("Sort by: "), sorter_selector]].spacing(5),
+            self.view_export(),
             text(format!("Found {} package(s)", self.packages.len())),
             packages_display
         ]
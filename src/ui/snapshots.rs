@@ -0,0 +1,179 @@
+use iced::Task;
+use iced::widget::{Column, button, column, row, scrollable, text};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::AppMessage;
+use crate::logic::server::Server;
+use crate::logic::snapshot::{self, Snapshot, SnapshotDiff};
+
+const LIVE: &str = "Live system";
+
+//Saves the installed set with versions and reasons and compares two snapshots, or one with the live system
+#[derive(Debug, Clone)]
+pub struct SnapshotsView {
+    pub server: Arc<Mutex<Server>>,
+    pub dir: PathBuf,
+    //File stems of the saved snapshots, oldest first
+    pub snapshots: Vec<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub diff: Option<Result<SnapshotDiff, String>>,
+    pub loading: bool,
+}
+
+impl Default for SnapshotsView {
+    fn default() -> Self {
+        SnapshotsView {
+            server: Arc::default(),
+            dir: snapshot::snapshot_dir(),
+            snapshots: vec![],
+            from: None,
+            to: Some(LIVE.to_string()),
+            diff: None,
+            loading: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SnapshotMessage {
+    Load,
+    Take,
+    Taken(Result<PathBuf, String>),
+    FromSelected(String),
+    ToSelected(String),
+    Compare,
+    Compared(Result<SnapshotDiff, String>),
+}
+
+//A saved snapshot by file stem, or the installed set right now
+fn resolve(server: &Arc<Mutex<Server>>, dir: &Path, name: &str) -> Result<Snapshot, String> {
+    if name == LIVE {
+        let packages = server.lock().unwrap().installed();
+        return Ok(Snapshot::capture(&packages, LIVE.to_string()));
+    }
+    snapshot::load(&dir.join(format!("{}.json", name)))
+}
+
+impl SnapshotsView {
+    pub fn update(&mut self, message: AppMessage) -> Task<AppMessage> {
+        match message {
+            AppMessage::SnapshotMessage(m) => match m {
+                SnapshotMessage::Load => {
+                    self.snapshots = snapshot::list(&self.dir)
+                        .iter()
+                        .filter_map(|p| p.file_stem())
+                        .map(|s| s.to_string_lossy().to_string())
+                        .collect();
+                    if self.from.is_none() {
+                        self.from = self.snapshots.last().cloned();
+                    }
+                    Task::none()
+                }
+                SnapshotMessage::Take => {
+                    self.loading = true;
+                    let server = self.server.clone();
+                    let dir = self.dir.clone();
+                    Task::perform(
+                        async move {
+                            let packages = server.lock().unwrap().installed();
+                            let created = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+                            snapshot::save(&dir, &Snapshot::capture(&packages, created))
+                        },
+                        |r| AppMessage::SnapshotMessage(SnapshotMessage::Taken(r)),
+                    )
+                }
+                SnapshotMessage::Taken(result) => {
+                    self.loading = false;
+                    match result {
+                        Ok(path) => self.from = path.file_stem().map(|s| s.to_string_lossy().to_string()),
+                        Err(e) => {
+                            let _ = native_dialog::MessageDialog::new().set_text(&e).set_title("An error has ocurred :(").show_alert();
+                        }
+                    }
+                    self.update(AppMessage::SnapshotMessage(SnapshotMessage::Load))
+                }
+                SnapshotMessage::FromSelected(name) => {
+                    self.from = Some(name);
+                    Task::none()
+                }
+                SnapshotMessage::ToSelected(name) => {
+                    self.to = Some(name);
+                    Task::none()
+                }
+                SnapshotMessage::Compare => {
+                    let (Some(from), Some(to)) = (self.from.clone(), self.to.clone()) else {
+                        return Task::none();
+                    };
+                    self.loading = true;
+                    let server = self.server.clone();
+                    let dir = self.dir.clone();
+                    Task::perform(
+                        async move {
+                            let from = resolve(&server, &dir, &from)?;
+                            let to = resolve(&server, &dir, &to)?;
+                            Ok(from.diff(&to))
+                        },
+                        |r| AppMessage::SnapshotMessage(SnapshotMessage::Compared(r)),
+                    )
+                }
+                SnapshotMessage::Compared(diff) => {
+                    self.loading = false;
+                    self.diff = Some(diff);
+                    Task::none()
+                }
+            },
+            _ => Task::none(),
+        }
+    }
+
+    fn section<'a>(title: &str, entries: Vec<String>) -> Column<'a, AppMessage> {
+        column![
+            text(format!("{} ({})", title, entries.len())),
+            column(entries.into_iter().map(|x| text(x).size(12).into())).spacing(2),
+        ]
+        .spacing(5)
+    }
+
+    pub fn view(&self) -> Column<'_, AppMessage> {
+        let mut options = self.snapshots.clone();
+        options.push(LIVE.to_string());
+
+        let body: iced::Element<AppMessage> = match &self.diff {
+            _ if self.loading => iced_aw::Spinner::new().circle_radius(20.0).into(),
+            None => text(format!("{} snapshot(s) in {}", self.snapshots.len(), self.dir.display())).into(),
+            Some(Err(e)) => text(e.clone()).style(text::danger).into(),
+            Some(Ok(diff)) if diff.is_empty() => text("No differences").into(),
+            Some(Ok(diff)) => scrollable(
+                column![
+                    Self::section("Added", diff.added.iter().map(|e| format!("{} {} ({})", e.name, e.version, e.reason)).collect()),
+                    Self::section("Removed", diff.removed.iter().map(|e| format!("{} {} ({})", e.name, e.version, e.reason)).collect()),
+                    Self::section("Version changed", diff.versions.iter().map(|c| format!("{} {} -> {}", c.name, c.old, c.new)).collect()),
+                    Self::section("Install reason changed", diff.reasons.iter().map(|c| format!("{} {} -> {}", c.name, c.old, c.new)).collect()),
+                ]
+                .spacing(20),
+            )
+            .height(iced::Length::Fill)
+            .into(),
+        };
+
+        column![
+            row![
+                button("Take snapshot").on_press_maybe((!self.loading).then_some(AppMessage::SnapshotMessage(SnapshotMessage::Take))),
+                iced::widget::horizontal_space(),
+                iced::widget::pick_list(options.clone(), self.from.clone(), |s| AppMessage::SnapshotMessage(SnapshotMessage::FromSelected(s))),
+                text("→"),
+                iced::widget::pick_list(options, self.to.clone(), |s| AppMessage::SnapshotMessage(SnapshotMessage::ToSelected(s))),
+                button("Compare").on_press_maybe(
+                    (!self.loading && self.from.is_some() && self.to.is_some())
+                        .then_some(AppMessage::SnapshotMessage(SnapshotMessage::Compare))
+                ),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            body,
+        ]
+        .spacing(10)
+    }
+}
@@ -0,0 +1,241 @@
+use iced::Task;
+use iced::widget::{Column, button, column, row, scrollable, text};
+use std::sync::{Arc, Mutex};
+
+use crate::AppMessage;
+use crate::logic::keyring;
+use crate::logic::prompts::{self, Answers, Question};
+use crate::logic::server::Server;
+use crate::logic::transaction::{Transaction, TransactionPreview};
+use crate::ui::lock;
+use crate::ui::search::SearchMessage;
+
+//Shows what a requested transaction resolves to and runs it once the user confirms
+#[derive(Debug, Clone, Default)]
+pub struct TransactionView {
+    pub server: Arc<Mutex<Server>>,
+    pub pending: Option<Transaction>,
+    pub preview: Option<Result<TransactionPreview, String>>,
+    //Choices pacman would make silently under --noconfirm, answered here before confirming
+    pub questions: Vec<Question>,
+    pub answers: Answers,
+    pub loading: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum TransactionMessage {
+    Request(Transaction),
+    Previewed(Result<TransactionPreview, String>, Vec<Question>),
+    ProviderChosen(String, String),
+    ReplaceConflictsToggled(bool),
+    ImportKeysToggled(bool),
+    Confirm,
+    Cancel,
+    Finished(String),
+}
+
+impl TransactionView {
+    //Resolves the pending transaction with the current answers applied, the questions are found again since
+    //another provider can bring conflicts or unknown packagers of its own
+    fn run_preview(&mut self) -> Task<AppMessage> {
+        let Some(mut transaction) = self.pending.clone() else {
+            return Task::none();
+        };
+        self.answers.apply(&self.questions, &mut transaction);
+        self.loading = true;
+        let server = self.server.clone();
+        Task::perform(
+            async move {
+                let preview = transaction.preview();
+                let questions = match &preview {
+                    Ok(preview) => {
+                        let packages = {
+                            let server = server.lock().unwrap();
+                            server.with_sync_entries(server.installed())
+                        };
+                        let keys = keyring::list_keys().ok();
+                        prompts::questions(&packages, &transaction, &preview.install, keys.as_deref())
+                    }
+                    Err(_) => vec![],
+                };
+                (preview, questions)
+            },
+            |(p, q)| AppMessage::TransactionMessage(TransactionMessage::Previewed(p, q)),
+        )
+    }
+
+    pub fn update(&mut self, message: AppMessage) -> Task<AppMessage> {
+        match message {
+            AppMessage::TransactionMessage(m) => match m {
+                TransactionMessage::Request(transaction) => {
+                    if self.loading || transaction.is_empty() {
+                        return Task::none();
+                    }
+                    self.pending = Some(transaction);
+                    self.preview = None;
+                    self.questions.clear();
+                    self.answers = Answers::default();
+                    self.run_preview()
+                }
+                TransactionMessage::Previewed(preview, questions) => {
+                    self.preview = Some(preview);
+                    self.questions = self.answers.revise(&self.questions, questions);
+                    self.loading = false;
+                    Task::none()
+                }
+                TransactionMessage::ProviderChosen(dependency, provider) => {
+                    if self.answers.providers.get(&dependency) == Some(&provider) {
+                        return Task::none();
+                    }
+                    self.answers.providers.insert(dependency, provider);
+                    self.run_preview()
+                }
+                TransactionMessage::ReplaceConflictsToggled(b) => {
+                    self.answers.replace_conflicts = b;
+                    Task::none()
+                }
+                TransactionMessage::ImportKeysToggled(b) => {
+                    self.answers.skip_key_import = !b;
+                    Task::none()
+                }
+                TransactionMessage::Cancel => {
+                    if !self.loading {
+                        self.pending = None;
+                        self.preview = None;
+                        self.questions.clear();
+                    }
+                    Task::none()
+                }
+                TransactionMessage::Confirm => {
+                    let Some(mut transaction) = self.pending.clone() else {
+                        return Task::none();
+                    };
+                    if let Some(wait) = lock::wait_if_locked(AppMessage::TransactionMessage(TransactionMessage::Confirm)) {
+                        return wait;
+                    }
+                    self.answers.apply(&self.questions, &mut transaction);
+                    self.loading = true;
+                    Task::perform(async move { transaction.commit() }, |stderror| {
+                        AppMessage::TransactionMessage(TransactionMessage::Finished(stderror))
+                    })
+                }
+                TransactionMessage::Finished(stderror) => {
+                    if !stderror.is_empty() {
+                        let _ = native_dialog::MessageDialog::new().set_text(&keyring::with_hint(stderror)).set_title("An error has ocurred :(").show_alert();
+                    } else {
+                        let _ = native_dialog::MessageDialog::new().set_title("Transaction finished succesfully").set_text("No errors were reported").show_alert();
+                    }
+                    self.loading = false;
+                    self.pending = None;
+                    self.preview = None;
+                    self.questions.clear();
+
+                    //Installed state changed, refresh it and rerun the current search
+                    let server = self.server.clone();
+                    Task::perform(
+                        async move {
+                            server.lock().unwrap().check_installed();
+                        },
+                        |_| AppMessage::SearchMessage(SearchMessage::SearchSubmited),
+                    )
+                }
+            },
+            _ => Task::none(),
+        }
+    }
+
+    pub fn view(&self) -> Column<'_, AppMessage> {
+        let description = text(
+            self.pending
+                .as_ref()
+                .map(|t| t.description.clone())
+                .unwrap_or_default(),
+        )
+        .size(20);
+
+        let notes = column(
+            self.pending
+                .iter()
+                .flat_map(|t| t.notes.iter())
+                .map(|x| text(x.clone()).into()),
+        )
+        .spacing(2);
+
+        let list = |title: &str, entries: &Vec<String>| {
+            column![
+                text(format!("{} ({})", title, entries.len())),
+                column(entries.iter().map(|x| text(x.clone()).into())).spacing(2),
+            ]
+            .spacing(5)
+        };
+
+        let mut questions = column![].spacing(10);
+        for question in self.questions.iter() {
+            let prompt: iced::Element<AppMessage> = match question {
+                Question::Provider { dependency, providers, .. } => {
+                    let dependency = dependency.clone();
+                    iced::widget::pick_list(providers.clone(), self.answers.providers.get(&dependency).cloned(), move |p| {
+                        AppMessage::TransactionMessage(TransactionMessage::ProviderChosen(dependency.clone(), p))
+                    })
+                    .into()
+                }
+                //pacman's --ask answers every conflict, and every key import, the same way
+                Question::Conflict { .. } | Question::ImportKey { .. } => {
+                    questions = questions.push(text(question.describe()));
+                    continue;
+                }
+            };
+            questions = questions.push(column![text(question.describe()), prompt].spacing(5));
+        }
+        let conflicts = self.questions.iter().filter(|q| matches!(q, Question::Conflict { .. })).count();
+        if conflicts > 0 {
+            questions = questions.push(
+                iced::widget::checkbox(format!("Remove the {} conflicting package(s)", conflicts), self.answers.replace_conflicts)
+                    .on_toggle(|b| AppMessage::TransactionMessage(TransactionMessage::ReplaceConflictsToggled(b))),
+            );
+        }
+        if self.questions.iter().any(|q| matches!(q, Question::ImportKey { .. })) {
+            questions = questions.push(
+                iced::widget::checkbox("Import the missing keys", !self.answers.skip_key_import)
+                    .on_toggle(|b| AppMessage::TransactionMessage(TransactionMessage::ImportKeysToggled(b))),
+            );
+        }
+
+        let body: iced::Element<AppMessage> = match &self.preview {
+            _ if self.loading => iced_aw::Spinner::new().circle_radius(20.0).into(),
+            Some(Ok(preview)) => scrollable(
+                column![
+                    questions,
+                    list("To install", &preview.install),
+                    list("To remove", &preview.remove)
+                ]
+                .spacing(20),
+            )
+            .height(iced::Length::Fill)
+            .into(),
+            Some(Err(error)) => text(format!("The transaction can not be performed:\n{}", error)).into(),
+            None => text("").into(),
+        };
+
+        //pacman aborts on a conflict it is not allowed to resolve
+        let unresolved = !self.answers.replace_conflicts && self.questions.iter().any(|q| matches!(q, Question::Conflict { .. }));
+        let can_confirm = !self.loading && !unresolved && matches!(self.preview, Some(Ok(_)));
+
+        column![
+            description,
+            notes,
+            body,
+            row![
+                button("Confirm").on_press_maybe(
+                    can_confirm.then_some(AppMessage::TransactionMessage(TransactionMessage::Confirm))
+                ),
+                button("Cancel").on_press_maybe(
+                    (!self.loading).then_some(AppMessage::TransactionMessage(TransactionMessage::Cancel))
+                ),
+            ]
+            .spacing(10)
+        ]
+        .spacing(20)
+        .width(iced::Length::Fill)
+    }
+}